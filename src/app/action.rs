@@ -0,0 +1,794 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::state::{App, Mode, PromptKind};
+use crate::{
+    models::{
+        filter::Filter,
+        todo::{format_due, parse_due, parse_tags, Priority},
+        view::{SortCriteria, SortSpec},
+    },
+    store::todo_store::TodoFields,
+    ui::{
+        edit_popup::{EditingState, InputFields, SelectableField},
+        line_input::LineInput,
+        text_area::TextArea,
+    },
+};
+
+/// Something the app can be asked to do, whether by a key, the mouse, the
+/// command line or a test; `App::handle` carries it out. What an action does
+/// can depend on the mode, e.g. `Close` leaves the trash, the views or the
+/// search results.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    SelectPrevious,
+    SelectNext,
+    NewTodo,
+    AddSubtask,
+    Edit,
+    EditExternally,
+    ToggleDone,
+    Delete,
+    ClearCompleted,
+    Collapse,
+    Expand,
+    ToggleCollapsed,
+    Search,
+    FullTextSearch,
+    Filter,
+    TagFilter,
+    PickView,
+    SaveView,
+    /// Sort by this first, then by the previous sort
+    SortBy(SortCriteria),
+    ReverseSort,
+    EditSort,
+    MoveDown,
+    MoveUp,
+    PreviousProject,
+    NextProject,
+    NewProject,
+    RenameProject,
+    ArchiveProject,
+    MoveToProject,
+    OpenTrash,
+    NextTheme,
+    Undo,
+    Redo,
+    Save,
+    Submit,
+    Cancel,
+    NextField,
+    FocusField(SelectableField),
+    SetPriority(Priority),
+    NextPriority,
+    PreviousPriority,
+    AcceptSuggestion,
+    Close,
+    Restore,
+    Purge,
+    PurgeOlderThan,
+    NextMatch,
+    PreviousMatch,
+    EditSearch,
+    ToggleCase,
+    ToggleRegex,
+    ApplyView,
+    DeleteView,
+    OpenResult,
+    ToggleArchived,
+    /// Select the row of the todo table, counted from the top
+    SelectRow(usize),
+    /// Select the row of the todo table and mark its todo done or open
+    ToggleDoneAt(usize),
+    /// A key typed into whichever input has focus
+    Input(KeyEvent),
+    /// Text pasted into whichever input has focus
+    Paste(String),
+    AddTodo {
+        fields: TodoFields,
+        parent_id: Option<i64>,
+    },
+    UpdateTodo {
+        id: i64,
+        fields: TodoFields,
+    },
+    /// Mark the todo with this id done, if it isn't already
+    CompleteTodo(i64),
+    DeleteTodo(i64),
+}
+
+/// What's left for the caller to do once an action is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    None,
+    Quit,
+    /// Clear the screen before the next draw
+    Redraw,
+    /// Edit the todo at this index in `$VISUAL` or `$EDITOR`
+    EditExternally(usize),
+    /// A todo was added with this id
+    Added(i64),
+}
+
+impl App {
+    /// Carries out `action` in the current mode. Actions that make no sense
+    /// in the mode are ignored; those that fail return an error to show.
+    pub fn handle(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        match action {
+            Action::AddTodo { fields, parent_id } => {
+                let id = match parent_id {
+                    Some(parent_id) => self.add_subtask(parent_id, fields)?,
+                    None => self.add_todo(fields)?,
+                };
+                Ok(Effect::Added(id))
+            }
+            Action::UpdateTodo { id, fields } => {
+                self.update_todo(self.find_todo(id)?, fields)?;
+                Ok(Effect::None)
+            }
+            Action::CompleteTodo(id) => {
+                let index = self.find_todo(id)?;
+                if !self.store.todos()[index].completed {
+                    self.toggle_todo(index)?;
+                }
+                Ok(Effect::None)
+            }
+            Action::DeleteTodo(id) => {
+                self.delete_todo(self.find_todo(id)?)?;
+                Ok(Effect::None)
+            }
+            Action::Paste(text) => {
+                self.paste(&text);
+                Ok(Effect::None)
+            }
+            action => match self.mode {
+                Mode::Normal => self.handle_normal(action),
+                Mode::Editing => self.handle_editing(action),
+                Mode::Trash => self.handle_trash(action),
+                Mode::Search => self.handle_search(action),
+                Mode::Views => self.handle_views(action),
+                Mode::SearchResults => self.handle_results(action),
+                Mode::Prompt => self.handle_prompt(action),
+            },
+        }
+    }
+
+    fn handle_normal(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        match action {
+            Action::Quit => return Ok(Effect::Quit),
+            Action::Undo => match self.undo() {
+                Ok(true) => {}
+                Ok(false) => return Err("Nothing to undo".into()),
+                Err(e) => return Err(format!("Failed to undo: {}", e).into()),
+            },
+            Action::Redo => match self.redo() {
+                Ok(true) => {}
+                Ok(false) => return Err("Nothing to redo".into()),
+                Err(e) => return Err(format!("Failed to redo: {}", e).into()),
+            },
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::NewTodo => {
+                let tags = self.query.tags.join(", ");
+                self.start_editing(self.config.default_priority, tags);
+            }
+            Action::AddSubtask => {
+                if let Some(selected) = self.selected_index() {
+                    let parent = &self.store.todos()[selected];
+                    let priority = parent.priority.unwrap_or(self.config.default_priority);
+                    let (id, tags) = (parent.id, parent.tags.join(", "));
+                    self.start_editing(priority, tags);
+                    self.editing_state.parent_id = Some(id);
+                }
+            }
+            Action::Edit => {
+                if let Some(todo) = self.selected_index().map(|i| &self.store.todos()[i]) {
+                    self.editing_state = EditingState {
+                        input_fields: InputFields {
+                            text: LineInput::new(&todo.text),
+                            priority: todo.priority,
+                            due: LineInput::new(&format_due(todo.due_date, todo.due_time)),
+                            tags: LineInput::new(&todo.tags.join(", ")),
+                            notes: TextArea::new(&todo.notes),
+                        },
+                        selected_field: Some(SelectableField::Text),
+                        parent_id: None,
+                        last_selected: 0,
+                    };
+                    self.mode = Mode::Editing;
+                }
+            }
+            Action::EditExternally => {
+                if let Some(selected) = self.selected_index() {
+                    return Ok(Effect::EditExternally(selected));
+                }
+            }
+            Action::Collapse => self.collapse_selected(),
+            Action::Expand => self.expand_selected(),
+            Action::ToggleCollapsed => self.toggle_collapsed(),
+            Action::Search => self.open_search(),
+            Action::FullTextSearch => self.open_prompt(PromptKind::FullTextSearch),
+            Action::Filter => {
+                let input = self
+                    .query
+                    .filter
+                    .as_ref()
+                    .map(|f| f.source().to_string())
+                    .unwrap_or_default();
+                self.open_prompt_with(PromptKind::Filter, input);
+            }
+            Action::TagFilter => {
+                let input = self.query.tags.join(", ");
+                self.open_prompt_with(PromptKind::TagFilter, input);
+            }
+            Action::PickView => self.open_views(),
+            Action::SaveView => {
+                // offer to overwrite the active view unless it's built in
+                let name = self
+                    .active_saved_view()
+                    .map(|v| v.name.clone())
+                    .unwrap_or_default();
+                self.open_prompt_with(PromptKind::SaveView, name);
+            }
+            Action::Delete => {
+                if let Some(selected) = self.selected_index() {
+                    self.delete_todo(selected)
+                        .map_err(|e| format!("Failed to delete todo: {}", e))?;
+                }
+            }
+            Action::ClearCompleted => self
+                .clear_completed()
+                .map_err(|e| format!("Failed to clear completed todos: {}", e))?,
+            Action::OpenTrash => self
+                .open_trash()
+                .map_err(|e| format!("Failed to load trash: {}", e))?,
+            Action::PreviousProject => self.cycle_project(false),
+            Action::NextProject => self.cycle_project(true),
+            Action::NewProject => self.open_prompt(PromptKind::NewProject),
+            Action::RenameProject => {
+                let name = self
+                    .active_project()
+                    .map(|p| p.name.clone())
+                    .unwrap_or_default();
+                self.open_prompt_with(PromptKind::RenameProject, name);
+            }
+            Action::ArchiveProject => self
+                .archive_active_project()
+                .map_err(|e| format!("Failed to archive project: {}", e))?,
+            Action::MoveToProject if self.selected_index().is_some() => {
+                self.open_prompt(PromptKind::MoveToProject);
+            }
+            Action::ToggleDone => {
+                if let Some(selected) = self.selected_index() {
+                    self.toggle_todo(selected)
+                        .map_err(|e| format!("Failed to toggle todo: {}", e))?;
+                }
+            }
+            Action::SelectPrevious => self.select_previous(),
+            Action::SelectNext => self.select_next(),
+            Action::SelectRow(row) => self.select_row(row),
+            // a click below the last row must not toggle the selected todo
+            Action::ToggleDoneAt(row) if row < self.visible.len() => {
+                self.select_row(row);
+                return self.handle_normal(Action::ToggleDone);
+            }
+            Action::ToggleDoneAt(_) => return Ok(Effect::None),
+            Action::SortBy(criteria) => self
+                .set_sort_criteria(criteria)
+                .map_err(|e| format!("Failed to save the sort: {}", e))?,
+            Action::ReverseSort => self
+                .toggle_sort_direction()
+                .map_err(|e| format!("Failed to save the sort: {}", e))?,
+            Action::EditSort => {
+                let input = self.sort.to_string();
+                self.open_prompt_with(PromptKind::Sort, input);
+            }
+            Action::MoveDown | Action::MoveUp => self
+                .move_selected(action == Action::MoveUp)
+                .map_err(|e| format!("Failed to move todo: {}", e))?,
+            Action::NextTheme => self
+                .next_theme()
+                .map_err(|e| format!("Failed to save the theme: {}", e))?,
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    fn handle_editing(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        let state = &mut self.editing_state;
+        match action {
+            Action::Save | Action::Submit => return self.save_editing(),
+            Action::Cancel => {
+                state.input_fields.clear();
+                state.parent_id = None;
+                self.mode = Mode::Normal;
+            }
+            Action::NextField => {
+                state.selected_field = Some(match state.selected_field {
+                    Some(SelectableField::Text) => SelectableField::Priority,
+                    Some(SelectableField::Priority) => SelectableField::Due,
+                    Some(SelectableField::Due) => SelectableField::Tags,
+                    Some(SelectableField::Tags) => SelectableField::Notes,
+                    Some(SelectableField::Notes) | None => SelectableField::Text,
+                });
+            }
+            Action::FocusField(field) => state.selected_field = Some(field),
+            Action::SetPriority(priority) => {
+                state.input_fields.priority = Some(priority);
+                state.selected_field = Some(SelectableField::Priority);
+            }
+            Action::NextPriority | Action::PreviousPriority => {
+                let current = state.input_fields.priority.unwrap_or(Priority::Medium);
+                // down the list wraps from low back to high
+                let next = match (current, action == Action::NextPriority) {
+                    (Priority::High, true) | (Priority::Low, false) => Priority::Medium,
+                    (Priority::Medium, true) | (Priority::High, false) => Priority::Low,
+                    (Priority::Low, true) | (Priority::Medium, false) => Priority::High,
+                };
+                state.input_fields.priority = Some(next);
+            }
+            Action::AcceptSuggestion => {
+                let tags = self.editing_state.input_fields.tags.value();
+                if let Some(suggestion) = self.suggest_tag(tags).map(String::from) {
+                    self.editing_state.input_fields.tags.insert_str(&suggestion);
+                }
+            }
+            Action::Input(key) => {
+                if state.selected_field == Some(SelectableField::Notes) {
+                    state.input_fields.notes.handle_key(key);
+                } else if let Some(input) = state.focused_input() {
+                    input.handle_key(key);
+                }
+            }
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    fn handle_trash(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        match action {
+            Action::Quit => return Ok(Effect::Quit),
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::Close => self.close_trash(),
+            Action::SelectPrevious => self.select_previous_deleted(),
+            Action::SelectNext => self.select_next_deleted(),
+            Action::Restore => {
+                if let Some(selected) = self.trash_state.selected() {
+                    self.restore_from_trash(selected)
+                        .map_err(|e| format!("Failed to restore todo: {}", e))?;
+                }
+            }
+            Action::Purge => {
+                if let Some(selected) = self.trash_state.selected() {
+                    self.purge_from_trash(selected)
+                        .map_err(|e| format!("Failed to purge todo: {}", e))?;
+                }
+            }
+            Action::PurgeOlderThan => self.open_prompt(PromptKind::PurgeOlderThan),
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    fn handle_search(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        let Some(search) = self.search.as_mut() else {
+            return Ok(Effect::None);
+        };
+
+        if search.typing {
+            match action {
+                Action::Cancel => self.close_search(true),
+                Action::Submit => {
+                    search.typing = false;
+                    if search.input.is_empty() {
+                        self.close_search(true);
+                    }
+                }
+                Action::ToggleCase => {
+                    search.case_sensitive = !search.case_sensitive;
+                    self.update_search();
+                }
+                Action::ToggleRegex => {
+                    search.regex = !search.regex;
+                    self.update_search();
+                }
+                Action::Input(key) if search.input.handle_key(key) => self.update_search(),
+                _ => {}
+            }
+            return Ok(Effect::None);
+        }
+
+        match action {
+            Action::Quit => return Ok(Effect::Quit),
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::Close => self.close_search(false),
+            Action::EditSearch => {
+                search.typing = true;
+                search.origin = self.table_state.selected();
+            }
+            Action::NextMatch => self.step_search(true),
+            Action::PreviousMatch => self.step_search(false),
+            Action::SelectPrevious => self.select_previous(),
+            Action::SelectNext => self.select_next(),
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    fn handle_views(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        match action {
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::Close => self.close_views(),
+            Action::SelectPrevious => self.select_previous_view(),
+            Action::SelectNext => self.select_next_view(),
+            Action::DeleteView => {
+                if let Some(selected) = self.views_state.selected() {
+                    self.delete_view(selected)
+                        .map_err(|e| format!("Failed to delete view: {}", e))?;
+                }
+            }
+            Action::ApplyView => {
+                if let Some(selected) = self.views_state.selected() {
+                    self.close_views();
+                    self.apply_view(selected)
+                        .map_err(|e| format!("Failed to apply view: {}", e))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    fn handle_results(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        match action {
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::Close => self.close_results(),
+            Action::SelectPrevious => self.select_previous_result(),
+            Action::SelectNext => self.select_next_result(),
+            Action::ToggleArchived => self
+                .toggle_results_archived()
+                .map_err(|e| format!("Failed to search: {}", e))?,
+            Action::OpenResult => self.open_selected_result()?,
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    fn handle_prompt(&mut self, action: Action) -> Result<Effect, Box<dyn std::error::Error>> {
+        match action {
+            Action::Cancel => {
+                self.close_prompt();
+            }
+            Action::Submit => {
+                if let Some(prompt) = self.close_prompt() {
+                    self.submit_prompt(prompt.kind, prompt.input.trim())?;
+                }
+            }
+            Action::Input(key) => {
+                if let Some(prompt) = self.prompt.as_mut() {
+                    match key.code {
+                        KeyCode::Char(c) => prompt.input.push(c),
+                        KeyCode::Backspace => {
+                            prompt.input.pop();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(Effect::None)
+    }
+
+    // opens the edit popup for a new todo, remembering the selected row to
+    // return to once it's saved
+    fn start_editing(&mut self, priority: Priority, tags: String) {
+        self.editing_state = EditingState {
+            input_fields: InputFields {
+                text: LineInput::default(),
+                priority: Some(priority),
+                due: LineInput::default(),
+                tags: LineInput::new(&tags),
+                notes: TextArea::default(),
+            },
+            selected_field: Some(SelectableField::Text),
+            parent_id: None,
+            last_selected: self.table_state.selected().unwrap_or(0),
+        };
+        self.table_state.select(None);
+        self.mode = Mode::Editing;
+    }
+
+    // saves the todo in the edit popup, updating the selected todo or adding
+    // a new one, and closes the popup
+    fn save_editing(&mut self) -> Result<Effect, Box<dyn std::error::Error>> {
+        let fields = &self.editing_state.input_fields;
+        let (due_date, due_time) = parse_due(fields.due.value())?;
+
+        let fields = TodoFields {
+            text: fields.text.value().to_string(),
+            priority: fields.priority,
+            due_date,
+            due_time,
+            tags: parse_tags(fields.tags.value()),
+            notes: fields.notes.text(),
+        };
+        // checked here too so the popup shows the reason without a prefix
+        fields.validate()?;
+
+        if let Some(selected) = self.selected_index() {
+            self.update_todo(selected, fields)
+                .map_err(|e| format!("Failed to update todo: {}", e))?;
+        } else {
+            match self.editing_state.parent_id {
+                Some(parent_id) => self.add_subtask(parent_id, fields),
+                None => self.add_todo(fields),
+            }
+            .map_err(|e| format!("Failed to add todo: {}", e))?;
+            self.table_state
+                .select(Some(self.editing_state.last_selected));
+        }
+
+        self.editing_state.input_fields.clear();
+        self.editing_state.parent_id = None;
+        self.mode = Mode::Normal;
+        Ok(Effect::Redraw)
+    }
+
+    fn open_prompt_with(&mut self, kind: PromptKind, input: String) {
+        self.open_prompt(kind);
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.input = input;
+        }
+    }
+
+    fn submit_prompt(
+        &mut self,
+        kind: PromptKind,
+        input: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match kind {
+            PromptKind::TagFilter => self.set_tag_filter(parse_tags(input)),
+            PromptKind::Filter if input.is_empty() => self.set_filter(None),
+            PromptKind::Filter => self.set_filter(Some(Filter::parse(input)?)),
+            PromptKind::Sort => {
+                let sort = if input.is_empty() {
+                    self.config.default_sort.clone()
+                } else {
+                    input.parse::<SortSpec>()?
+                };
+                self.set_sort(sort)
+                    .map_err(|e| format!("Failed to save the sort: {}", e))?;
+            }
+            PromptKind::SaveView => self
+                .save_view(input)
+                .map_err(|e| format!("Failed to save view: {}", e))?,
+            PromptKind::NewProject => self
+                .create_project(input)
+                .map_err(|e| format!("Failed to create project: {}", e))?,
+            PromptKind::RenameProject => self
+                .rename_active_project(input)
+                .map_err(|e| format!("Failed to rename project: {}", e))?,
+            PromptKind::MoveToProject => {
+                if let Some(selected) = self.selected_index() {
+                    self.move_todo_to_project(selected, input)
+                        .map_err(|e| format!("Failed to move todo: {}", e))?;
+                }
+            }
+            PromptKind::PurgeOlderThan => {
+                let days = input
+                    .parse::<u64>()
+                    .map_err(|_| format!("'{}' is not a number of days", input))?;
+                self.purge_trash_older_than(days)
+                    .map_err(|e| format!("Failed to purge trash: {}", e))?;
+            }
+            PromptKind::FullTextSearch => {
+                if !input.is_empty() {
+                    self.run_full_text_search(input, false)
+                        .map_err(|e| format!("Failed to search: {}", e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts pasted text into whichever text field has focus
+    fn paste(&mut self, text: &str) {
+        match self.mode {
+            Mode::Editing => {
+                if self.editing_state.selected_field == Some(SelectableField::Notes) {
+                    self.editing_state.input_fields.notes.insert_str(text);
+                } else if let Some(input) = self.editing_state.focused_input() {
+                    input.insert_str(text);
+                }
+            }
+            Mode::Prompt => {
+                if let Some(prompt) = self.prompt.as_mut() {
+                    prompt
+                        .input
+                        .push_str(text.lines().next().unwrap_or_default());
+                }
+            }
+            Mode::Search => {
+                if let Some(search) = self.search.as_mut() {
+                    if search.typing {
+                        search.input.insert_str(text);
+                        self.update_search();
+                    }
+                }
+            }
+            Mode::Normal | Mode::Trash | Mode::SearchResults | Mode::Views => {}
+        }
+    }
+
+    fn find_todo(&self, id: i64) -> Result<usize, String> {
+        self.index_of(id)
+            .ok_or_else(|| format!("No todo with id {}", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn app() -> App {
+        App::new(":memory:", Config::default()).unwrap()
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle(Action::Input(KeyEvent::from(KeyCode::Char(c))))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn new_todo_is_saved_with_the_picked_priority() {
+        let mut app = app();
+        app.handle(Action::NewTodo).unwrap();
+        assert!(app.mode == Mode::Editing);
+        type_text(&mut app, "Buy milk");
+
+        app.handle(Action::NextField).unwrap();
+        let priority = |app: &App| app.editing_state.input_fields.priority;
+        assert_eq!(priority(&app), Some(Priority::Medium));
+        app.handle(Action::NextPriority).unwrap();
+        assert_eq!(priority(&app), Some(Priority::Low));
+        app.handle(Action::NextPriority).unwrap();
+        assert_eq!(priority(&app), Some(Priority::High));
+        app.handle(Action::PreviousPriority).unwrap();
+        assert_eq!(priority(&app), Some(Priority::Low));
+        app.handle(Action::SetPriority(Priority::High)).unwrap();
+
+        assert_eq!(app.handle(Action::Save).unwrap(), Effect::Redraw);
+        assert!(app.mode == Mode::Normal);
+        assert_eq!(app.store.todos().len(), 1);
+        assert_eq!(app.store.todos()[0].text, "Buy milk");
+        assert_eq!(app.store.todos()[0].priority, Some(Priority::High));
+    }
+
+    #[test]
+    fn next_field_cycles_through_every_field() {
+        let mut app = app();
+        app.handle(Action::NewTodo).unwrap();
+
+        let mut seen = vec![app.editing_state.selected_field];
+        for _ in 0..5 {
+            app.handle(Action::NextField).unwrap();
+            seen.push(app.editing_state.selected_field);
+        }
+        assert_eq!(
+            seen,
+            [
+                SelectableField::Text,
+                SelectableField::Priority,
+                SelectableField::Due,
+                SelectableField::Tags,
+                SelectableField::Notes,
+                SelectableField::Text,
+            ]
+            .map(Some)
+        );
+
+        // typing goes to the focused field
+        app.handle(Action::FocusField(SelectableField::Tags))
+            .unwrap();
+        type_text(&mut app, "home");
+        assert_eq!(app.editing_state.input_fields.tags.value(), "home");
+        assert!(app.editing_state.input_fields.text.is_empty());
+    }
+
+    #[test]
+    fn invalid_input_keeps_the_popup_open() {
+        let mut app = app();
+        app.handle(Action::NewTodo).unwrap();
+        let error = app.handle(Action::Save).unwrap_err();
+        assert_eq!(error.to_string(), "A todo needs some text");
+
+        type_text(&mut app, "Call the bank");
+        app.handle(Action::FocusField(SelectableField::Due))
+            .unwrap();
+        type_text(&mut app, "someday");
+        assert!(app.handle(Action::Submit).is_err());
+        assert!(app.mode == Mode::Editing);
+        assert!(app.store.todos().is_empty());
+
+        app.handle(Action::Cancel).unwrap();
+        assert!(app.mode == Mode::Normal);
+        assert!(app.store.todos().is_empty());
+    }
+
+    #[test]
+    fn editing_updates_the_selected_todo() {
+        let mut app = app();
+        let Effect::Added(id) = app
+            .handle(Action::AddTodo {
+                fields: TodoFields::new("Water plants"),
+                parent_id: None,
+            })
+            .unwrap()
+        else {
+            panic!("no todo was added");
+        };
+
+        app.handle(Action::Edit).unwrap();
+        type_text(&mut app, " twice");
+        app.handle(Action::Submit).unwrap();
+
+        assert_eq!(app.store.todos().len(), 1);
+        assert_eq!(app.store.todos()[0].id, id);
+        assert_eq!(app.store.todos()[0].text, "Water plants twice");
+    }
+
+    #[test]
+    fn todos_can_be_completed_and_deleted_by_id() {
+        let mut app = app();
+        let add = |app: &mut App, text| {
+            app.handle(Action::AddTodo {
+                fields: TodoFields::new(text),
+                parent_id: None,
+            })
+            .unwrap()
+        };
+        let Effect::Added(id) = add(&mut app, "Pay rent") else {
+            panic!("no todo was added");
+        };
+
+        // completing twice leaves it done
+        app.handle(Action::CompleteTodo(id)).unwrap();
+        app.handle(Action::CompleteTodo(id)).unwrap();
+        assert!(app.store.todos()[0].completed);
+
+        app.handle(Action::DeleteTodo(id)).unwrap();
+        assert!(app.store.todos().is_empty());
+        assert!(app.handle(Action::DeleteTodo(id)).is_err());
+
+        app.handle(Action::Undo).unwrap();
+        assert_eq!(app.store.todos().len(), 1);
+    }
+
+    #[test]
+    fn clicking_below_the_last_row_toggles_nothing() {
+        let mut app = app();
+        for text in ["Pay rent", "Call mum"] {
+            app.handle(Action::AddTodo {
+                fields: TodoFields::new(text),
+                parent_id: None,
+            })
+            .unwrap();
+        }
+        app.handle(Action::SelectRow(0)).unwrap();
+        let done = |app: &App| app.store.todos().iter().filter(|t| t.completed).count();
+
+        app.handle(Action::ToggleDoneAt(2)).unwrap();
+        assert_eq!(done(&app), 0);
+        assert_eq!(app.table_state.selected(), Some(0));
+
+        app.handle(Action::ToggleDoneAt(1)).unwrap();
+        assert_eq!(done(&app), 1);
+        assert!(app.store.todos()[app.visible[1]].completed);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::models::todo::{format_due, parse_due, parse_tags, Todo};
+use crate::store::todo_store::TodoFields;
+
+const DELIMITER: &str = "---";
+
+/// Writes a todo in the plain text format used to edit it in an external
+/// editor: `key: value` fields between `---` lines, followed by the notes.
+///
+/// ```text
+/// ---
+/// text: Write release notes
+/// priority: high
+/// due: 2026-02-01 17:00
+/// tags: docs, release
+/// ---
+/// Anything below the second `---` becomes the notes.
+/// ```
+pub fn format_todo(todo: &Todo) -> String {
+    let mut output = format!(
+        "{delim}\ntext: {}\npriority: {}\ndue: {}\ntags: {}\n{delim}\n",
+        todo.text,
+        todo.priority.map(|p| p.to_string()).unwrap_or_default(),
+        format_due(todo.due_date, todo.due_time),
+        todo.tags.join(", "),
+        delim = DELIMITER,
+    );
+    if !todo.notes.is_empty() {
+        output.push_str(&todo.notes);
+        output.push('\n');
+    }
+    output
+}
+
+/// Parses the output of `format_todo` after the user has edited it. Fields
+/// that were removed are left empty, apart from `text` which is required.
+pub fn parse_todo(input: &str) -> Result<TodoFields, String> {
+    let mut lines = input.lines().enumerate();
+
+    match lines.next() {
+        Some((_, line)) if line.trim() == DELIMITER => {}
+        _ => return Err(format!("Expected the file to start with '{}'", DELIMITER)),
+    }
+
+    let mut text = None;
+    let mut priority = None;
+    let mut due = (None, None);
+    let mut tags = Vec::new();
+    let mut closed = false;
+
+    for (number, line) in lines.by_ref() {
+        if line.trim() == DELIMITER {
+            closed = true;
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(format!(
+                "Line {}: expected 'field: value', found '{}'",
+                number + 1,
+                line
+            ));
+        };
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "text" => text = Some(value.to_string()),
+            "priority" if value.is_empty() => priority = None,
+            "priority" => priority = Some(value.parse()?),
+            "due" => due = parse_due(value)?,
+            "tags" => tags = parse_tags(value),
+            other => return Err(format!("Line {}: unknown field '{}'", number + 1, other)),
+        }
+    }
+
+    if !closed {
+        return Err(format!(
+            "Expected a closing '{}' after the fields",
+            DELIMITER
+        ));
+    }
+
+    let text = match text {
+        Some(text) if !text.is_empty() => text,
+        _ => return Err(String::from("The 'text' field must not be empty")),
+    };
+
+    let notes: Vec<&str> = lines.map(|(_, line)| line).collect();
+
+    Ok(TodoFields {
+        text,
+        priority,
+        due_date: due.0,
+        due_time: due.1,
+        tags,
+        notes: notes.join("\n").trim_end().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn todo(fields: TodoFields) -> Todo {
+        Todo {
+            id: 1,
+            text: fields.text,
+            completed: false,
+            created_at: Local::now(),
+            completed_at: None,
+            priority: fields.priority,
+            due_date: fields.due_date,
+            due_time: fields.due_time,
+            deleted_at: None,
+            tags: fields.tags,
+            project_id: 1,
+            parent_id: None,
+            notes: fields.notes,
+            position: 0,
+        }
+    }
+
+    #[test]
+    fn formatted_todos_parse_back_unchanged() {
+        let full = TodoFields {
+            priority: Some(Priority::High),
+            due_date: NaiveDate::from_ymd_opt(2026, 2, 1),
+            due_time: NaiveTime::from_hms_opt(17, 0, 0),
+            tags: vec![String::from("docs"), String::from("release")],
+            notes: String::from("Mention the new\n\n  indented\nformat"),
+            ..TodoFields::new("Write release notes")
+        };
+        let bare = TodoFields {
+            priority: None,
+            ..TodoFields::new("Water plants")
+        };
+
+        for fields in [full, bare] {
+            assert_eq!(parse_todo(&format_todo(&todo(fields.clone()))), Ok(fields));
+        }
+    }
+
+    #[test]
+    fn the_fields_need_both_delimiters() {
+        assert_eq!(
+            parse_todo("text: Buy milk\n---\n"),
+            Err(String::from("Expected the file to start with '---'"))
+        );
+        assert_eq!(
+            parse_todo("---\ntext: Buy milk\n"),
+            Err(String::from("Expected a closing '---' after the fields"))
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert_eq!(
+            parse_todo("---\ntext: Buy milk\nowner: me\n---\n"),
+            Err(String::from("Line 3: unknown field 'owner'"))
+        );
+        assert_eq!(
+            parse_todo("---\ntext: Buy milk\nsoon\n---\n"),
+            Err(String::from(
+                "Line 3: expected 'field: value', found 'soon'"
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert_eq!(
+            parse_todo("---\ntext: Buy milk\npriority: urgent\n---\n"),
+            Err(String::from(
+                "Invalid priority 'urgent': expected high, medium or low"
+            ))
+        );
+        assert_eq!(
+            parse_todo("---\ntext: Buy milk\ndue: 2026-13-01\n---\n"),
+            Err(String::from(
+                "Invalid due date '2026-13-01': expected YYYY-MM-DD"
+            ))
+        );
+    }
+
+    #[test]
+    fn the_text_must_not_be_empty() {
+        for input in ["---\ntext:\n---\n", "---\npriority: low\n---\n"] {
+            assert_eq!(
+                parse_todo(input),
+                Err(String::from("The 'text' field must not be empty"))
+            );
+        }
+    }
+}
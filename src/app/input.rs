@@ -0,0 +1,161 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
+
+use super::{
+    action::Action,
+    keymap::{KeyChord, KeyContext, KeyPress, PRIORITY_KEYS},
+    state::{App, Mode},
+};
+use crate::ui::edit_popup::SelectableField;
+
+/// Turns terminal events into actions, using the keymap for keys and what
+/// was drawn in the last frame for the mouse
+#[derive(Default)]
+pub struct Input {
+    /// The keys of a sequence pressed so far
+    pending: Vec<KeyChord>,
+}
+
+impl Input {
+    /// The action `event` stands for in the app's current state, if any
+    pub fn action(&mut self, app: &App, event: Event) -> Option<Action> {
+        match event {
+            Event::Key(key) => self.key_action(app, key),
+            Event::Mouse(mouse) => {
+                self.pending.clear();
+                mouse_action(app, mouse)
+            }
+            Event::Paste(text) => Some(Action::Paste(text)),
+            _ => None,
+        }
+    }
+
+    fn key_action(&mut self, app: &App, key: KeyEvent) -> Option<Action> {
+        let context = app.key_context();
+        let field = app.editing_state.selected_field;
+        match app
+            .config
+            .keys
+            .press(context, &mut self.pending, key.into())
+        {
+            // enter starts a new line in the notes, and right only takes the
+            // suggested tag at the end of the tags
+            KeyPress::Action(Action::Submit | Action::AcceptSuggestion)
+                if field == Some(SelectableField::Notes) && context == KeyContext::Editing =>
+            {
+                Some(Action::Input(key))
+            }
+            KeyPress::Action(Action::AcceptSuggestion)
+                if field != Some(SelectableField::Tags)
+                    || !app.editing_state.input_fields.tags.cursor_at_end() =>
+            {
+                Some(Action::Input(key))
+            }
+            KeyPress::Action(action) => Some(action),
+            KeyPress::Pending => None,
+            KeyPress::Unbound => match context {
+                KeyContext::Editing if field == Some(SelectableField::Priority) => {
+                    priority_action(key)
+                }
+                KeyContext::Editing | KeyContext::SearchInput | KeyContext::Prompt => {
+                    Some(Action::Input(key))
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+// picks the priority by its initial or number, or moves through them with j/k
+fn priority_action(key: KeyEvent) -> Option<Action> {
+    let KeyCode::Char(c) = key.code else {
+        return None;
+    };
+    PRIORITY_KEYS
+        .iter()
+        .find(|priority_key| priority_key.keys.contains(&c))
+        .map(|priority_key| priority_key.action.clone())
+}
+
+/// Clicks select and toggle todos, sort by the clicked column and focus
+/// fields in the edit popup; the wheel moves the selection
+fn mouse_action(app: &App, mouse: MouseEvent) -> Option<Action> {
+    let position = Position::new(mouse.column, mouse.row);
+    let layout = &app.layout;
+    match (&app.mode, mouse.kind) {
+        (Mode::Normal, MouseEventKind::Down(MouseButton::Left)) => {
+            if let Some(criteria) = layout.sort_header_at(position) {
+                return Some(Action::SortBy(criteria));
+            }
+            let row = layout.todo_row_at(position, app.table_state.offset())?;
+            if layout.on_status(position) {
+                Some(Action::ToggleDoneAt(row))
+            } else {
+                Some(Action::SelectRow(row))
+            }
+        }
+        (Mode::Editing, MouseEventKind::Down(MouseButton::Left)) => layout
+            .priority_at(position)
+            .map(Action::SetPriority)
+            .or_else(|| layout.field_at(position).map(Action::FocusField)),
+        (
+            Mode::Normal | Mode::Trash | Mode::SearchResults | Mode::Views,
+            MouseEventKind::ScrollDown,
+        ) => Some(Action::SelectNext),
+        (
+            Mode::Normal | Mode::Trash | Mode::SearchResults | Mode::Views,
+            MouseEventKind::ScrollUp,
+        ) => Some(Action::SelectPrevious),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, models::todo::Priority};
+
+    fn press(input: &mut Input, app: &App, code: KeyCode) -> Option<Action> {
+        input.action(app, Event::Key(KeyEvent::from(code)))
+    }
+
+    #[test]
+    fn enter_saves_except_in_the_notes() {
+        let mut app = App::new(":memory:", Config::default()).unwrap();
+        let mut input = Input::default();
+        app.handle(Action::NewTodo).unwrap();
+
+        assert_eq!(
+            press(&mut input, &app, KeyCode::Enter),
+            Some(Action::Submit)
+        );
+        app.handle(Action::FocusField(SelectableField::Notes))
+            .unwrap();
+        assert_eq!(
+            press(&mut input, &app, KeyCode::Enter),
+            Some(Action::Input(KeyEvent::from(KeyCode::Enter)))
+        );
+    }
+
+    #[test]
+    fn letters_pick_the_priority_in_the_priority_field() {
+        let mut app = App::new(":memory:", Config::default()).unwrap();
+        let mut input = Input::default();
+        app.handle(Action::NewTodo).unwrap();
+
+        let h = KeyCode::Char('h');
+        assert_eq!(
+            press(&mut input, &app, h),
+            Some(Action::Input(KeyEvent::from(h)))
+        );
+        app.handle(Action::NextField).unwrap();
+        assert_eq!(
+            press(&mut input, &app, h),
+            Some(Action::SetPriority(Priority::High))
+        );
+        assert_eq!(
+            press(&mut input, &app, KeyCode::Char('j')),
+            Some(Action::NextPriority)
+        );
+    }
+}
@@ -0,0 +1,1065 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+
+use super::action::Action;
+use crate::models::{todo::Priority, view::SortCriteria};
+
+/// Where keys are pressed, each with its own bindings. These are the modes of
+/// the app, except that typing a search and browsing its matches differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyContext {
+    Normal,
+    Editing,
+    Trash,
+    Search,
+    SearchInput,
+    Views,
+    SearchResults,
+    Prompt,
+}
+
+// an action as bound before the config has its say
+struct DefaultBinding {
+    action: Action,
+    name: &'static str,
+    keys: &'static [&'static str],
+    help: &'static str,
+}
+
+const fn bind(
+    action: Action,
+    name: &'static str,
+    keys: &'static [&'static str],
+    help: &'static str,
+) -> DefaultBinding {
+    DefaultBinding {
+        action,
+        name,
+        keys,
+        help,
+    }
+}
+
+const NORMAL: &[DefaultBinding] = &[
+    bind(Action::NewTodo, "new_todo", &["n"], "new todo"),
+    bind(Action::AddSubtask, "add_subtask", &["a"], "add a subtask"),
+    bind(Action::Edit, "edit", &["e"], "edit the selected todo"),
+    bind(
+        Action::EditExternally,
+        "edit_externally",
+        &["E"],
+        "edit in $VISUAL/$EDITOR",
+    ),
+    bind(
+        Action::ToggleDone,
+        "toggle_done",
+        &["space"],
+        "mark done or open",
+    ),
+    bind(Action::Delete, "delete", &["d"], "delete the selected todo"),
+    bind(
+        Action::ClearCompleted,
+        "clear_completed",
+        &["c"],
+        "delete all completed todos",
+    ),
+    bind(
+        Action::SelectPrevious,
+        "select_previous",
+        &["k", "up"],
+        "previous todo",
+    ),
+    bind(
+        Action::SelectNext,
+        "select_next",
+        &["j", "down"],
+        "next todo",
+    ),
+    bind(Action::Collapse, "collapse", &["left"], "collapse subtasks"),
+    bind(Action::Expand, "expand", &["right"], "expand subtasks"),
+    bind(
+        Action::ToggleCollapsed,
+        "toggle_collapsed",
+        &["z"],
+        "collapse or expand subtasks",
+    ),
+    bind(Action::Search, "search", &["/"], "search todos"),
+    bind(
+        Action::FullTextSearch,
+        "full_text_search",
+        &["F"],
+        "search all projects and notes",
+    ),
+    bind(Action::Filter, "filter", &[":"], "filter with a query"),
+    bind(Action::TagFilter, "tag_filter", &["f"], "filter by tags"),
+    bind(Action::PickView, "pick_view", &["v"], "pick a view"),
+    bind(
+        Action::SaveView,
+        "save_view",
+        &["V"],
+        "save filter and sort as a view",
+    ),
+    bind(
+        Action::SortBy(SortCriteria::Priority),
+        "sort_by_priority",
+        &["p"],
+        "sort by priority first",
+    ),
+    bind(
+        Action::SortBy(SortCriteria::CreatedDate),
+        "sort_by_created",
+        &["t"],
+        "sort by created date first",
+    ),
+    bind(
+        Action::SortBy(SortCriteria::Completed),
+        "sort_by_status",
+        &["s"],
+        "sort by status first",
+    ),
+    bind(
+        Action::SortBy(SortCriteria::DueDate),
+        "sort_by_due",
+        &["w"],
+        "sort by due date first",
+    ),
+    bind(
+        Action::ReverseSort,
+        "reverse_sort",
+        &["r"],
+        "reverse the first sort key",
+    ),
+    bind(Action::EditSort, "edit_sort", &["o"], "edit the sort"),
+    bind(
+        Action::SortBy(SortCriteria::Manual),
+        "sort_manually",
+        &["M"],
+        "sort by hand",
+    ),
+    bind(
+        Action::MoveDown,
+        "move_down",
+        &["J"],
+        "move down when sorting by hand",
+    ),
+    bind(
+        Action::MoveUp,
+        "move_up",
+        &["K"],
+        "move up when sorting by hand",
+    ),
+    bind(
+        Action::PreviousProject,
+        "previous_project",
+        &["["],
+        "previous project",
+    ),
+    bind(Action::NextProject, "next_project", &["]"], "next project"),
+    bind(Action::NewProject, "new_project", &["P"], "new project"),
+    bind(
+        Action::RenameProject,
+        "rename_project",
+        &["R"],
+        "rename the project",
+    ),
+    bind(
+        Action::ArchiveProject,
+        "archive_project",
+        &["A"],
+        "archive the project",
+    ),
+    bind(
+        Action::MoveToProject,
+        "move_to_project",
+        &["m"],
+        "move todo to another project",
+    ),
+    bind(Action::OpenTrash, "open_trash", &["T"], "open the trash"),
+    bind(Action::NextTheme, "next_theme", &["C"], "next colour theme"),
+    bind(Action::Undo, "undo", &["u"], "undo"),
+    bind(Action::Redo, "redo", &["ctrl-r"], "redo"),
+    bind(
+        Action::ToggleHelp,
+        "toggle_help",
+        &["h", "?"],
+        "show or hide this help",
+    ),
+    bind(Action::Quit, "quit", &["q"], "quit"),
+];
+
+const EDITING: &[DefaultBinding] = &[
+    bind(
+        Action::Submit,
+        "submit",
+        &["enter"],
+        "save the todo, except in notes",
+    ),
+    bind(
+        Action::Save,
+        "save",
+        &["ctrl-s"],
+        "save the todo from any field",
+    ),
+    bind(
+        Action::NextField,
+        "next_field",
+        &["tab"],
+        "edit the next field",
+    ),
+    bind(
+        Action::AcceptSuggestion,
+        "accept_suggestion",
+        &["right"],
+        "accept the suggested tag at the end of tags",
+    ),
+    bind(Action::Cancel, "cancel", &["esc"], "cancel editing"),
+];
+
+/// A key that picks the priority while the priority field is focused
+pub struct PriorityKey {
+    pub keys: &'static [char],
+    pub action: Action,
+    pub help: &'static str,
+}
+
+/// The priority keys. Plain characters type text while editing, so these
+/// can't be bound; input matches on this table and the help describes it.
+pub const PRIORITY_KEYS: &[PriorityKey] = &[
+    PriorityKey {
+        keys: &['h', '1'],
+        action: Action::SetPriority(Priority::High),
+        help: "high",
+    },
+    PriorityKey {
+        keys: &['m', '2'],
+        action: Action::SetPriority(Priority::Medium),
+        help: "medium",
+    },
+    PriorityKey {
+        keys: &['l', '3'],
+        action: Action::SetPriority(Priority::Low),
+        help: "low",
+    },
+    PriorityKey {
+        keys: &['j'],
+        action: Action::NextPriority,
+        help: "next",
+    },
+    PriorityKey {
+        keys: &['k'],
+        action: Action::PreviousPriority,
+        help: "previous",
+    },
+];
+
+const TRASH: &[DefaultBinding] = &[
+    bind(
+        Action::Restore,
+        "restore",
+        &["r"],
+        "restore the selected todo",
+    ),
+    bind(Action::Purge, "purge", &["d"], "delete forever"),
+    bind(
+        Action::PurgeOlderThan,
+        "purge_older_than",
+        &["P"],
+        "delete forever if older than N days",
+    ),
+    bind(
+        Action::SelectPrevious,
+        "select_previous",
+        &["k", "up"],
+        "previous deleted todo",
+    ),
+    bind(
+        Action::SelectNext,
+        "select_next",
+        &["j", "down"],
+        "next deleted todo",
+    ),
+    bind(Action::Close, "close", &["esc", "T"], "back to todos"),
+    bind(
+        Action::ToggleHelp,
+        "toggle_help",
+        &["h", "?"],
+        "show or hide this help",
+    ),
+    bind(Action::Quit, "quit", &["q"], "quit"),
+];
+
+const SEARCH: &[DefaultBinding] = &[
+    bind(Action::NextMatch, "next_match", &["n"], "next match"),
+    bind(
+        Action::PreviousMatch,
+        "previous_match",
+        &["N"],
+        "previous match",
+    ),
+    bind(
+        Action::EditSearch,
+        "edit_search",
+        &["/"],
+        "change the search",
+    ),
+    bind(
+        Action::SelectPrevious,
+        "select_previous",
+        &["k", "up"],
+        "previous todo",
+    ),
+    bind(
+        Action::SelectNext,
+        "select_next",
+        &["j", "down"],
+        "next todo",
+    ),
+    bind(Action::Close, "close", &["esc", "enter"], "end the search"),
+    bind(
+        Action::ToggleHelp,
+        "toggle_help",
+        &["h", "?"],
+        "show or hide this help",
+    ),
+    bind(Action::Quit, "quit", &["q"], "quit"),
+];
+
+const SEARCH_INPUT: &[DefaultBinding] = &[
+    bind(Action::Submit, "submit", &["enter"], "confirm the search"),
+    bind(
+        Action::ToggleCase,
+        "toggle_case",
+        &["alt-c"],
+        "toggle case sensitivity",
+    ),
+    bind(
+        Action::ToggleRegex,
+        "toggle_regex",
+        &["alt-r"],
+        "toggle regex",
+    ),
+    bind(Action::Cancel, "cancel", &["esc"], "cancel the search"),
+];
+
+const VIEWS: &[DefaultBinding] = &[
+    bind(
+        Action::ApplyView,
+        "apply_view",
+        &["enter"],
+        "switch to the selected view",
+    ),
+    bind(
+        Action::DeleteView,
+        "delete_view",
+        &["d"],
+        "delete the selected saved view",
+    ),
+    bind(
+        Action::SelectPrevious,
+        "select_previous",
+        &["k", "up"],
+        "previous view",
+    ),
+    bind(
+        Action::SelectNext,
+        "select_next",
+        &["j", "down"],
+        "next view",
+    ),
+    bind(Action::Close, "close", &["esc", "q", "v"], "close"),
+    bind(
+        Action::ToggleHelp,
+        "toggle_help",
+        &["h", "?"],
+        "show or hide this help",
+    ),
+];
+
+const SEARCH_RESULTS: &[DefaultBinding] = &[
+    bind(
+        Action::OpenResult,
+        "open_result",
+        &["enter"],
+        "go to the selected todo",
+    ),
+    bind(
+        Action::ToggleArchived,
+        "toggle_archived",
+        &["a"],
+        "include deleted todos and archived projects",
+    ),
+    bind(
+        Action::SelectPrevious,
+        "select_previous",
+        &["k", "up"],
+        "previous result",
+    ),
+    bind(
+        Action::SelectNext,
+        "select_next",
+        &["j", "down"],
+        "next result",
+    ),
+    bind(Action::Close, "close", &["esc", "q"], "back to todos"),
+    bind(
+        Action::ToggleHelp,
+        "toggle_help",
+        &["h", "?"],
+        "show or hide this help",
+    ),
+];
+
+const PROMPT: &[DefaultBinding] = &[
+    bind(Action::Submit, "submit", &["enter"], "submit"),
+    bind(Action::Cancel, "cancel", &["esc"], "cancel"),
+];
+
+impl KeyContext {
+    const ALL: [KeyContext; 8] = [
+        KeyContext::Normal,
+        KeyContext::Editing,
+        KeyContext::Trash,
+        KeyContext::Search,
+        KeyContext::SearchInput,
+        KeyContext::Views,
+        KeyContext::SearchResults,
+        KeyContext::Prompt,
+    ];
+
+    /// The name of its table in the config, under `[keys]`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyContext::Normal => "normal",
+            KeyContext::Editing => "editing",
+            KeyContext::Trash => "trash",
+            KeyContext::Search => "search",
+            KeyContext::SearchInput => "search_input",
+            KeyContext::Views => "views",
+            KeyContext::SearchResults => "search_results",
+            KeyContext::Prompt => "prompt",
+        }
+    }
+
+    /// The heading of the help panel
+    pub fn title(&self) -> &'static str {
+        match self {
+            KeyContext::Normal => "Normal Mode Commands",
+            KeyContext::Editing => "Editing Mode Commands",
+            KeyContext::Trash => "Trash Commands",
+            KeyContext::Search | KeyContext::SearchInput => "Search Commands",
+            KeyContext::Views => "Views Commands",
+            KeyContext::SearchResults => "Search Results Commands",
+            KeyContext::Prompt => "Prompt Commands",
+        }
+    }
+
+    /// What the keys do that aren't bound to anything, for the help panel
+    pub fn hints(&self) -> Vec<String> {
+        match self {
+            KeyContext::Editing => {
+                let priority_keys: Vec<String> = PRIORITY_KEYS
+                    .iter()
+                    .map(|key| {
+                        let keys: Vec<String> = key.keys.iter().map(char::to_string).collect();
+                        format!("{} {}", keys.join("/"), key.help)
+                    })
+                    .collect();
+                vec![
+                    String::from("type to enter todo text"),
+                    String::from("left/right/home/end - move cursor, alt-b/alt-f - move by word"),
+                    String::from("delete - delete under cursor, ctrl-w - delete previous word"),
+                    format!("priority - {}", priority_keys.join(", ")),
+                    String::from("due date - YYYY-MM-DD [HH:MM], today or tomorrow"),
+                    String::from("tags - comma separated"),
+                    String::from("notes - multi-line, enter starts a new line"),
+                ]
+            }
+            KeyContext::SearchInput => vec![String::from(
+                "type to search todo text, jumping to the first match",
+            )],
+            _ => Vec::new(),
+        }
+    }
+
+    // whether unbound keys type text, so plain characters can't be bound
+    fn types_text(&self) -> bool {
+        matches!(
+            self,
+            KeyContext::Editing | KeyContext::SearchInput | KeyContext::Prompt
+        )
+    }
+
+    fn defaults(&self) -> &'static [DefaultBinding] {
+        match self {
+            KeyContext::Normal => NORMAL,
+            KeyContext::Editing => EDITING,
+            KeyContext::Trash => TRASH,
+            KeyContext::Search => SEARCH,
+            KeyContext::SearchInput => SEARCH_INPUT,
+            KeyContext::Views => VIEWS,
+            KeyContext::SearchResults => SEARCH_RESULTS,
+            KeyContext::Prompt => PROMPT,
+        }
+    }
+}
+
+/// A key with the modifiers held down, e.g. `ctrl-r`. Shift is part of the
+/// character, so `K` is shift-k.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        let mut modifiers = key.modifiers;
+        if matches!(key.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        KeyChord {
+            code: key.code,
+            modifiers,
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // a lone "-" is the minus key, not a separator
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", s)),
+                },
+            },
+        };
+
+        // fold shift into the key, the way terminals report it
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Ok(KeyChord { code, modifiers })
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Keys pressed one after the other, written with spaces between them,
+/// e.g. `g n`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence(Vec<KeyChord>);
+
+impl KeySequence {
+    fn starts_with(&self, keys: &[KeyChord]) -> bool {
+        self.0.starts_with(keys)
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chords = s
+            .split_whitespace()
+            .map(KeyChord::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if chords.is_empty() {
+            return Err(String::from("no key given"));
+        }
+        Ok(KeySequence(chords))
+    }
+}
+
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chords: Vec<String> = self.0.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", chords.join(" "))
+    }
+}
+
+/// What a key press amounts to
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyPress {
+    Action(Action),
+    /// The keys so far start a sequence, so wait for the next one
+    Pending,
+    /// Nothing is bound to the key
+    Unbound,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    context: KeyContext,
+    action: Action,
+    name: &'static str,
+    help: &'static str,
+    keys: Vec<KeySequence>,
+}
+
+/// The keys bound to each action, per context. Starts from the defaults
+/// below; a `[keys.<context>]` table in the config replaces the keys of the
+/// actions it names, e.g.
+///
+/// ```toml
+/// [keys.normal]
+/// new_todo = "i"              # one key
+/// select_next = ["j", "down"] # several keys
+/// quit = "g q"                # a sequence, pressed one after the other
+/// redo = "ctrl-r"             # with ctrl-, alt- or shift-
+/// archive_project = []        # not bound at all
+/// ```
+///
+/// `r_todo config` lists every context and action with its keys.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "HashMap<KeyContext, HashMap<String, KeyList>>")]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+// one key or several, as written in the config
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = KeyContext::ALL
+            .iter()
+            .flat_map(|&context| {
+                context.defaults().iter().map(move |default| Binding {
+                    context,
+                    action: default.action.clone(),
+                    name: default.name,
+                    help: default.help,
+                    keys: default
+                        .keys
+                        .iter()
+                        .map(|keys| keys.parse().expect("default keys are valid"))
+                        .collect(),
+                })
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// Adds `key` to the keys pressed so far and looks up what they're bound
+    /// to. Keys that lead nowhere are dropped, and the last one is tried on
+    /// its own.
+    pub fn press(
+        &self,
+        context: KeyContext,
+        pending: &mut Vec<KeyChord>,
+        key: KeyChord,
+    ) -> KeyPress {
+        pending.push(key);
+        let mut prefix = false;
+        for binding in self.in_context(context) {
+            for keys in &binding.keys {
+                if keys.0 == *pending {
+                    pending.clear();
+                    return KeyPress::Action(binding.action.clone());
+                }
+                prefix |= keys.starts_with(pending);
+            }
+        }
+
+        if prefix {
+            KeyPress::Pending
+        } else if pending.len() > 1 {
+            pending.clear();
+            self.press(context, pending, key)
+        } else {
+            pending.clear();
+            KeyPress::Unbound
+        }
+    }
+
+    /// The keys and description of every bound action in `context`
+    pub fn help(&self, context: KeyContext) -> Vec<(Action, String, &'static str)> {
+        self.in_context(context)
+            .filter(|binding| !binding.keys.is_empty())
+            .map(|binding| {
+                let keys: Vec<String> = binding.keys.iter().map(|k| k.to_string()).collect();
+                (binding.action.clone(), keys.join(" or "), binding.help)
+            })
+            .collect()
+    }
+
+    fn in_context(&self, context: KeyContext) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |b| b.context == context)
+    }
+
+    // rejects keys that are bound twice, that start a longer sequence or
+    // that would stop text from being typed
+    fn validate(&self) -> Result<(), String> {
+        for context in KeyContext::ALL {
+            let keys: Vec<(&Binding, &KeySequence)> = self
+                .in_context(context)
+                .flat_map(|binding| binding.keys.iter().map(move |keys| (binding, keys)))
+                .collect();
+
+            for (i, &(binding, sequence)) in keys.iter().enumerate() {
+                let typed = sequence.0.first().filter(|chord| {
+                    matches!(chord.code, KeyCode::Char(_))
+                        && !chord
+                            .modifiers
+                            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                });
+                if context.types_text() && typed.is_some() {
+                    return Err(format!(
+                        "'{}' for {} in [keys.{}] would stop it being typed, add ctrl- or alt-",
+                        sequence,
+                        binding.name,
+                        context.as_str()
+                    ));
+                }
+
+                for &(other, other_sequence) in &keys[i + 1..] {
+                    if sequence == other_sequence && binding.name == other.name {
+                        continue;
+                    }
+                    if sequence == other_sequence {
+                        return Err(format!(
+                            "'{}' is bound to both {} and {} in [keys.{}]",
+                            sequence,
+                            binding.name,
+                            other.name,
+                            context.as_str()
+                        ));
+                    }
+                    let (short, long) = if sequence.0.len() < other_sequence.0.len() {
+                        ((binding, sequence), (other, other_sequence))
+                    } else {
+                        ((other, other_sequence), (binding, sequence))
+                    };
+                    if long.1.starts_with(&short.1 .0) {
+                        return Err(format!(
+                            "'{}' for {} starts '{}' for {} in [keys.{}]",
+                            short.1,
+                            short.0.name,
+                            long.1,
+                            long.0.name,
+                            context.as_str()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<HashMap<KeyContext, HashMap<String, KeyList>>> for Keymap {
+    type Error = String;
+
+    fn try_from(
+        tables: HashMap<KeyContext, HashMap<String, KeyList>>,
+    ) -> Result<Self, Self::Error> {
+        let mut keymap = Keymap::default();
+        for (context, table) in tables {
+            for (name, list) in table {
+                let binding = keymap
+                    .bindings
+                    .iter_mut()
+                    .find(|b| b.context == context && b.name == name)
+                    .ok_or_else(|| {
+                        let names: Vec<&str> = context.defaults().iter().map(|d| d.name).collect();
+                        format!(
+                            "unknown action '{}' in [keys.{}], expected one of {}",
+                            name,
+                            context.as_str(),
+                            names.join(", ")
+                        )
+                    })?;
+
+                let list = match list {
+                    KeyList::One(keys) => vec![keys],
+                    KeyList::Many(list) => list,
+                };
+                binding.keys = list
+                    .iter()
+                    .map(|keys| {
+                        keys.parse().map_err(|e| {
+                            format!("{} for {} in [keys.{}]", e, name, context.as_str())
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+}
+
+// written out in full, one table per context, so `r_todo config` shows
+// every binding
+impl Serialize for Keymap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tables = serializer.serialize_map(Some(KeyContext::ALL.len()))?;
+        for context in KeyContext::ALL {
+            let table: Vec<(&str, Vec<String>)> = self
+                .in_context(context)
+                .map(|b| (b.name, b.keys.iter().map(|k| k.to_string()).collect()))
+                .collect();
+            tables.serialize_entry(context.as_str(), &BindingTable(table))?;
+        }
+        tables.end()
+    }
+}
+
+struct BindingTable<'a>(Vec<(&'a str, Vec<String>)>);
+
+impl Serialize for BindingTable<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut table = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, keys) in &self.0 {
+            match keys.as_slice() {
+                [keys] => table.serialize_entry(name, keys)?,
+                keys => table.serialize_entry(name, keys)?,
+            }
+        }
+        table.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(keys: &str) -> KeyChord {
+        keys.parse().unwrap()
+    }
+
+    fn keymap(config: &str) -> Result<Keymap, String> {
+        toml::from_str(config).map_err(|e| e.to_string())
+    }
+
+    fn press(keymap: &Keymap, pending: &mut Vec<KeyChord>, keys: &str) -> KeyPress {
+        keymap.press(KeyContext::Normal, pending, chord(keys))
+    }
+
+    #[test]
+    fn parses_keys_and_modifiers() {
+        let key = |code, modifiers| KeyChord { code, modifiers };
+        assert_eq!(chord("n"), key(KeyCode::Char('n'), KeyModifiers::NONE));
+        assert_eq!(
+            chord("ctrl-r"),
+            key(KeyCode::Char('r'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            chord("Ctrl-Alt-x"),
+            key(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(chord("space"), key(KeyCode::Char(' '), KeyModifiers::NONE));
+        assert_eq!(
+            chord("PageDown"),
+            key(KeyCode::PageDown, KeyModifiers::NONE)
+        );
+        assert_eq!(chord("f5"), key(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(chord("-"), key(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(
+            chord("ctrl--"),
+            key(KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+
+        assert!("f13".parse::<KeyChord>().is_err());
+        assert!("hyper-x".parse::<KeyChord>().is_err());
+        assert!("".parse::<KeySequence>().is_err());
+    }
+
+    #[test]
+    fn shift_is_folded_into_the_key() {
+        assert_eq!(chord("shift-k"), chord("K"));
+        assert_eq!(chord("shift-tab"), chord("backtab"));
+        assert_eq!(
+            KeyChord::from(KeyEvent::new(KeyCode::Char('K'), KeyModifiers::SHIFT)),
+            chord("K")
+        );
+        assert_eq!(
+            KeyChord::from(KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT)),
+            chord("shift-up")
+        );
+    }
+
+    #[test]
+    fn keys_are_written_the_way_they_are_parsed() {
+        for keys in [
+            "n",
+            "K",
+            "ctrl-r",
+            "alt-enter",
+            "space",
+            "shift-tab",
+            "f12",
+            "g n",
+        ] {
+            assert_eq!(keys.parse::<KeySequence>().unwrap().to_string(), keys);
+        }
+    }
+
+    #[test]
+    fn sequences_wait_for_their_next_key() {
+        let keymap = keymap("[normal]\nquit = \"g q\"").unwrap();
+        let mut pending = Vec::new();
+
+        assert_eq!(press(&keymap, &mut pending, "g"), KeyPress::Pending);
+        assert_eq!(
+            press(&keymap, &mut pending, "q"),
+            KeyPress::Action(Action::Quit)
+        );
+        assert!(pending.is_empty());
+
+        // a key that doesn't continue the sequence is tried on its own
+        assert_eq!(press(&keymap, &mut pending, "g"), KeyPress::Pending);
+        assert_eq!(
+            press(&keymap, &mut pending, "n"),
+            KeyPress::Action(Action::NewTodo)
+        );
+        assert!(pending.is_empty());
+
+        assert_eq!(press(&keymap, &mut pending, "q"), KeyPress::Unbound);
+    }
+
+    #[test]
+    fn rejects_keys_bound_twice_or_starting_a_sequence() {
+        let error = keymap("[normal]\nquit = \"n\"").unwrap_err();
+        assert!(
+            error.contains("'n' is bound to both new_todo and quit"),
+            "{}",
+            error
+        );
+
+        let error = keymap("[normal]\nquit = \"n q\"").unwrap_err();
+        assert!(
+            error.contains("'n' for new_todo starts 'n q' for quit"),
+            "{}",
+            error
+        );
+
+        // the same key twice for one action is harmless
+        assert!(keymap("[normal]\nquit = [\"q\", \"q\"]").is_ok());
+    }
+
+    #[test]
+    fn rejects_plain_characters_where_text_is_typed() {
+        let error = keymap("[editing]\nsave = \"s\"").unwrap_err();
+        assert!(error.contains("would stop it being typed"), "{}", error);
+        assert!(keymap("[editing]\nsave = \"alt-s\"").is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_actions_and_keys() {
+        let error = keymap("[normal]\nfly = \"f\"").unwrap_err();
+        assert!(
+            error.contains("unknown action 'fly' in [keys.normal]"),
+            "{}",
+            error
+        );
+
+        let error = keymap("[normal]\nquit = \"hyper-q\"").unwrap_err();
+        assert!(error.contains("unknown key 'hyper-q'"), "{}", error);
+    }
+
+    #[test]
+    fn an_empty_list_leaves_an_action_unbound() {
+        let keymap = keymap("[normal]\narchive_project = []").unwrap();
+        assert_eq!(press(&keymap, &mut Vec::new(), "A"), KeyPress::Unbound);
+        assert!(keymap
+            .help(KeyContext::Normal)
+            .iter()
+            .all(|(action, _, _)| *action != Action::ArchiveProject));
+    }
+
+    #[test]
+    fn the_editing_help_describes_the_priority_keys() {
+        assert!(KeyContext::Editing.hints().contains(&String::from(
+            "priority - h/1 high, m/2 medium, l/3 low, j next, k previous"
+        )));
+    }
+}
@@ -0,0 +1,59 @@
+use ratatui::layout::{Position, Rect};
+
+use crate::{
+    models::{todo::Priority, view::SortCriteria},
+    ui::edit_popup::SelectableField,
+};
+
+/// Where things were drawn in the last frame, so a mouse event can be matched
+/// to whatever is under the pointer. Rebuilt on every render; anything not
+/// drawn in the last frame is left empty.
+#[derive(Default)]
+pub struct ScreenLayout {
+    /// The rows of the todo table, below its header
+    pub todo_rows: Rect,
+    pub todo_row_height: u16,
+    /// The status cells of the todo rows
+    pub status_column: Rect,
+    /// Header cells that change the sort when clicked
+    pub sort_headers: Vec<(SortCriteria, Rect)>,
+    /// The fields of the edit popup
+    pub fields: Vec<(SelectableField, Rect)>,
+    /// The choices of the priority field in the edit popup
+    pub priorities: Vec<(Priority, Rect)>,
+}
+
+impl ScreenLayout {
+    /// The row of `App::visible` under the pointer, given how far the table
+    /// was scrolled
+    pub fn todo_row_at(&self, position: Position, offset: usize) -> Option<usize> {
+        if !self.todo_rows.contains(position) || self.todo_row_height == 0 {
+            return None;
+        }
+        let row = (position.y - self.todo_rows.y) / self.todo_row_height;
+        Some(offset + row as usize)
+    }
+
+    pub fn on_status(&self, position: Position) -> bool {
+        self.status_column.contains(position)
+    }
+
+    pub fn sort_header_at(&self, position: Position) -> Option<SortCriteria> {
+        find_at(&self.sort_headers, position)
+    }
+
+    pub fn field_at(&self, position: Position) -> Option<SelectableField> {
+        find_at(&self.fields, position)
+    }
+
+    pub fn priority_at(&self, position: Position) -> Option<Priority> {
+        find_at(&self.priorities, position)
+    }
+}
+
+fn find_at<T: Copy>(areas: &[(T, Rect)], position: Position) -> Option<T> {
+    areas
+        .iter()
+        .find(|(_, area)| area.contains(position))
+        .map(|&(item, _)| item)
+}
@@ -1 +1,7 @@
+pub mod action;
+pub mod front_matter;
+pub mod input;
+pub mod keymap;
+pub mod layout;
+pub mod search;
 pub mod state;
@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+use ratatui::widgets::TableState;
+use regex::{Regex, RegexBuilder};
+
+use crate::{models::todo::SearchHit, ui::line_input::LineInput};
+
+/// The state of an incremental `/` search over the visible todos
+pub struct SearchState {
+    pub input: LineInput,
+    /// treat the query as a regular expression rather than plain text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// true while the query is being typed, false once it has been
+    /// confirmed and `n`/`N` step through the matches
+    pub typing: bool,
+    /// the table row selected when the search started, returned to when the
+    /// query matches nothing or the search is cancelled
+    pub origin: Option<usize>,
+    /// why the query couldn't be compiled, if it couldn't
+    pub error: Option<String>,
+    matcher: Option<Regex>,
+}
+
+impl SearchState {
+    pub fn new(origin: Option<usize>) -> Self {
+        Self {
+            input: LineInput::default(),
+            regex: false,
+            case_sensitive: false,
+            typing: true,
+            origin,
+            error: None,
+            matcher: None,
+        }
+    }
+
+    /// Recompiles the matcher after the query or one of the options changed
+    pub fn update(&mut self) {
+        self.error = None;
+        self.matcher = None;
+
+        let query = self.input.value();
+        if query.is_empty() {
+            return;
+        }
+
+        let pattern = if self.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        match RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+        {
+            Ok(matcher) => self.matcher = Some(matcher),
+            Err(e) => {
+                // the first line names the problem, the rest draw the pattern
+                let message = e.to_string();
+                self.error = Some(message.lines().last().unwrap_or_default().to_string());
+            }
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.matcher.as_ref().is_some_and(|m| m.is_match(text))
+    }
+
+    /// Byte ranges of every non-empty match in `text`
+    pub fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.matcher {
+            Some(matcher) => matcher
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The results of a full-text search of the database, shown in their own view
+pub struct SearchResults {
+    pub query: String,
+    /// whether deleted todos and todos in archived projects were searched
+    pub include_archived: bool,
+    pub hits: Vec<SearchHit>,
+    pub state: TableState,
+}
@@ -1,86 +1,238 @@
+use super::{
+    action::Action,
+    keymap::KeyContext,
+    layout::ScreenLayout,
+    search::{SearchResults, SearchState},
+};
 use crate::{
-    db::handler::DatabaseHandler,
-    models::todo::{Priority, Todo},
-    ui::edit_popup::{EditingState, InputFields},
+    config::Config,
+    db::migrations::POSITION_GAP,
+    models::{
+        filter::Filter,
+        project::{Project, DEFAULT_PROJECT_ID},
+        todo::{SearchHit, Todo},
+        view::{builtin_views, SortCriteria, SortSpec, View},
+    },
+    store::{
+        query::Query,
+        todo_store::{TodoFields, TodoStore},
+    },
+    ui::{
+        color_scheme::ColorScheme,
+        color_support::ColorSupport,
+        edit_popup::{EditingState, InputFields},
+        line_input::LineInput,
+        text_area::TextArea,
+    },
+};
+use ratatui::{
+    text::Line,
+    widgets::{ListState, TableState},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
 };
-use chrono::Local;
-use ratatui::{text::Line, widgets::TableState};
-use std::time::{Duration, Instant};
 
 #[derive(Eq, PartialEq)]
 pub enum Mode {
     Normal,
     Editing,
+    Trash,
+    Prompt,
+    Search,
+    SearchResults,
+    Views,
 }
 
+/// What a single-line prompt is asking the user for
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub enum SortCriteria {
-    Priority,
-    Completed,
-    CreatedDate,
+pub enum PromptKind {
+    PurgeOlderThan,
+    TagFilter,
+    NewProject,
+    RenameProject,
+    MoveToProject,
+    FullTextSearch,
+    Filter,
+    SaveView,
+    Sort,
+}
+
+impl PromptKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PromptKind::PurgeOlderThan => "Purge items deleted more than N days ago",
+            PromptKind::TagFilter => "Show todos tagged with (empty to clear)",
+            PromptKind::NewProject => "New project name",
+            PromptKind::RenameProject => "Rename project to",
+            PromptKind::MoveToProject => "Move selected todo to project",
+            PromptKind::FullTextSearch => "Search text and notes of all todos",
+            PromptKind::Filter => ":filter (e.g. priority:high -done tag:infra, empty to clear)",
+            PromptKind::SaveView => "Save the current filter and sort as a view named",
+            PromptKind::Sort => "Sort by (e.g. status, -priority, created; empty for the default)",
+        }
+    }
+
+    /// The mode to return to once the prompt is submitted or cancelled
+    pub fn return_mode(&self) -> Mode {
+        match self {
+            PromptKind::PurgeOlderThan => Mode::Trash,
+            PromptKind::TagFilter
+            | PromptKind::NewProject
+            | PromptKind::RenameProject
+            | PromptKind::MoveToProject
+            | PromptKind::FullTextSearch
+            | PromptKind::Filter
+            | PromptKind::SaveView
+            | PromptKind::Sort => Mode::Normal,
+        }
+    }
+}
+
+// the settings keys holding the name of the last applied view, the last sort
+// picked by hand and the last theme picked
+const LAST_VIEW_SETTING: &str = "last_view";
+const SORT_SETTING: &str = "sort";
+const THEME_SETTING: &str = "theme";
+
+pub struct PromptState {
+    pub kind: PromptKind,
+    pub input: String,
 }
 
 pub struct App {
-    pub todos: Vec<Todo>,
+    /// The todos, projects and undo history, kept in the database
+    pub store: TodoStore,
+    /// Indexes into `store.todos()` of the rows shown in the table, in
+    /// display order. `table_state` selects a position in this list.
+    pub visible: Vec<usize>,
+    /// Nesting level of each row in `visible`
+    pub depths: Vec<usize>,
+    /// Ids of todos whose subtasks are hidden
+    pub collapsed: HashSet<i64>,
+    progress: HashMap<i64, (usize, usize)>,
+    /// Which todos are shown: those of the active project (every project
+    /// for `None`) carrying any of the filtered tags and matching the filter
+    pub query: Query,
     pub mode: Mode,
     pub table_state: TableState,
     pub editing_state: EditingState,
-    pub sort_by: SortCriteria,
-    pub sort_asc: bool,
+    pub sort: SortSpec,
     pub show_help: bool,
     pub error_message: Option<Vec<String>>,
     pub error_shown_at: Option<Instant>,
-    db: DatabaseHandler,
+    pub trash: Vec<Todo>,
+    pub trash_state: TableState,
+    pub prompt: Option<PromptState>,
+    pub search: Option<SearchState>,
+    pub results: Option<SearchResults>,
+    /// The built-in views followed by the saved ones
+    pub views: Vec<View>,
+    /// Name of the view last applied, until the filter or sort is changed
+    pub active_view: Option<String>,
+    pub views_state: ListState,
+    /// Where everything was drawn in the last frame
+    pub layout: ScreenLayout,
+    /// The colours of the active theme
+    pub colors: ColorScheme,
+    /// Index of the active theme in `config.themes`
+    theme: usize,
+    /// What the terminal can show, which the theme colours are adapted to
+    color_support: ColorSupport,
+    pub config: Config,
 }
 
 impl App {
-    pub fn new(db_path: &str) -> Result<App, Box<dyn std::error::Error>> {
-        let db = DatabaseHandler::new(db_path)?;
-        let todos = db.load_todos()?;
+    pub fn new(db_path: &str, config: Config) -> Result<App, Box<dyn std::error::Error>> {
+        let store = TodoStore::open(db_path)?;
+        let mut views = builtin_views();
+        views.extend(store.views()?);
+        let theme = config.find_theme(&config.theme).unwrap_or(0);
+        let color_support = config.colors.resolve();
 
-        Ok(App {
-            todos,
+        let mut app = App {
+            query: Query {
+                project: store.projects().first().map(|p| p.id),
+                ..Query::default()
+            },
+            store,
+            visible: Vec::new(),
+            depths: Vec::new(),
+            collapsed: HashSet::new(),
+            progress: HashMap::new(),
             mode: Mode::Normal,
             table_state: TableState::default().with_selected(0),
             editing_state: EditingState {
                 input_fields: InputFields {
-                    text: String::new(),
+                    text: LineInput::default(),
                     priority: None,
+                    due: LineInput::default(),
+                    tags: LineInput::default(),
+                    notes: TextArea::default(),
                 },
                 selected_field: None,
+                parent_id: None,
+                last_selected: 0,
             },
-            sort_by: SortCriteria::Priority,
-            sort_asc: true,
+            sort: config.default_sort.clone(),
             show_help: false,
             error_message: None,
             error_shown_at: None,
-            db,
-        })
+            trash: Vec::new(),
+            trash_state: TableState::default(),
+            prompt: None,
+            search: None,
+            results: None,
+            views,
+            active_view: None,
+            views_state: ListState::default(),
+            layout: ScreenLayout::default(),
+            colors: config.themes[theme].colors.clone().adapt(color_support),
+            theme,
+            color_support,
+            config,
+        };
+        app.sort_todos();
+
+        Ok(app)
     }
 
-    pub fn get_help_text(&self) -> Vec<Line<'_>> {
+    /// Which bindings of the keymap apply in the current mode
+    pub fn key_context(&self) -> KeyContext {
         match self.mode {
-            Mode::Normal => vec![
-                Line::from("Normal Mode Commands:"),
-                Line::from("n - new todo"),
-                Line::from("e - edit selected todo"),
-                Line::from("d - delete selected todo"),
-                Line::from("c - clear completed todos"),
-                Line::from("h - toggle help menu"),
-                Line::from("space - toggle todo completion"),
-                Line::from("k/j - navigate todos"),
-                Line::from("q - quit application"),
-            ],
-            Mode::Editing => vec![
-                Line::from("Editing Mode Commands:"),
-                Line::from("type to enter todo text"),
-                Line::from("tab - edit next field"),
-                Line::from("enter - save todo"),
-                Line::from("esc - cancel editing"),
-            ],
+            Mode::Normal => KeyContext::Normal,
+            Mode::Editing => KeyContext::Editing,
+            Mode::Trash => KeyContext::Trash,
+            Mode::Search if self.search.as_ref().is_some_and(|s| s.typing) => {
+                KeyContext::SearchInput
+            }
+            Mode::Search => KeyContext::Search,
+            Mode::Views => KeyContext::Views,
+            Mode::SearchResults => KeyContext::SearchResults,
+            Mode::Prompt => KeyContext::Prompt,
         }
     }
 
+    /// The help for the current mode, made from the keys bound in it
+    pub fn get_help_text(&self) -> Vec<Line<'_>> {
+        let context = self.key_context();
+        let mut lines: Vec<Line> = self
+            .config
+            .keys
+            .help(context)
+            .into_iter()
+            .map(|(action, keys, help)| match action {
+                Action::NextTheme => {
+                    Line::from(format!("{} - {} (now {})", keys, help, self.theme_name()))
+                }
+                _ => Line::from(format!("{} - {}", keys, help)),
+            })
+            .collect();
+        lines.extend(context.hints().into_iter().map(Line::from));
+        lines
+    }
+
     pub fn set_error(&mut self, message: String) {
         let error_lines: Vec<String> = message.lines().map(String::from).collect();
         self.error_message = Some(error_lines);
@@ -89,127 +241,983 @@ impl App {
 
     pub fn check_error_timeout(&mut self) {
         if let Some(shown_at) = self.error_shown_at {
-            if shown_at.elapsed() > Duration::from_secs(15) {
+            if shown_at.elapsed() > self.config.error_timeout() {
                 self.error_message = None;
                 self.error_shown_at = None;
             }
         }
     }
 
-    pub fn add_todo(
+    /// Adds a todo to the active project, returning its id
+    pub fn add_todo(&mut self, fields: TodoFields) -> Result<i64, Box<dyn std::error::Error>> {
+        let project_id = self.query.project.unwrap_or(DEFAULT_PROJECT_ID);
+        let id = self.store_mut().add(fields, project_id)?;
+        self.sort_todos();
+        Ok(id)
+    }
+
+    /// Adds a todo as a subtask of the todo with id `parent_id`, in the same project
+    pub fn add_subtask(
+        &mut self,
+        parent_id: i64,
+        fields: TodoFields,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let id = self.store_mut().add_subtask(parent_id, fields)?;
+        // make sure the new subtask is visible
+        self.collapsed.remove(&parent_id);
+        self.sort_todos();
+        Ok(id)
+    }
+
+    /// Toggles completion of a todo. Completing a todo also completes all of
+    /// its open subtasks; reopening it leaves the subtasks as they are.
+    pub fn toggle_todo(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(todo) = self.store.todos().get(index) else {
+            return Ok(());
+        };
+
+        let (id, completed) = (todo.id, todo.completed);
+        let result = if completed {
+            self.store_mut().reopen(id)
+        } else {
+            self.store_mut().complete(id)
+        };
+        self.changed();
+        Ok(result?)
+    }
+
+    /// Moves a todo and all of its subtasks to the trash
+    pub fn delete_todo(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(id) = self.store.todos().get(index).map(|t| t.id) {
+            let result = self.store_mut().delete(id);
+            self.changed();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Moves every completed todo to the trash. A completed todo that still
+    /// has open subtasks is kept, so clearing never throws away open work.
+    pub fn clear_completed(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self.store_mut().clear_completed();
+        self.changed();
+        Ok(result?)
+    }
+
+    // the store, with the selected todo in focus so that undoing the change
+    // about to be made selects it again
+    fn store_mut(&mut self) -> &mut TodoStore {
+        let selected_id = self.selected_id();
+        self.store.set_focus(selected_id);
+        &mut self.store
+    }
+
+    // shows the todos as they are after a change, which may have failed halfway
+    fn changed(&mut self) {
+        self.sort_todos();
+        self.clamp_selection();
+    }
+
+    /// Finds the position of a todo in `store.todos()` by its database id
+    pub fn index_of(&self, id: i64) -> Option<usize> {
+        self.store.index_of(id)
+    }
+
+    /// The index into `store.todos()` of the selected table row
+    pub fn selected_index(&self) -> Option<usize> {
+        self.table_state
+            .selected()
+            .and_then(|row| self.visible.get(row))
+            .copied()
+    }
+
+    fn selected_id(&self) -> Option<i64> {
+        self.selected_index().map(|i| self.store.todos()[i].id)
+    }
+
+    /// Reverts the most recent operation. Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let undone = self.store.undo();
+        self.select_by_id(self.store.focus());
+        Ok(undone?)
+    }
+
+    /// Re-applies the most recently undone operation. Returns false if there was nothing to redo.
+    pub fn redo(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let redone = self.store.redo();
+        self.select_by_id(self.store.focus());
+        Ok(redone?)
+    }
+
+    fn select_by_id(&mut self, id: Option<i64>) {
+        self.sort_todos();
+        let row = id
+            .and_then(|id| self.index_of(id))
+            .and_then(|index| self.visible.iter().position(|&i| i == index));
+        if row.is_some() {
+            self.table_state.select(row);
+        } else {
+            self.clamp_selection();
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        let selected = match self.table_state.selected() {
+            _ if self.visible.is_empty() => None,
+            Some(i) => Some(i.min(self.visible.len() - 1)),
+            None => Some(0),
+        };
+        self.table_state.select(selected);
+    }
+
+    /// Suggests a completion for the last, partially typed tag in `input`
+    pub fn suggest_tag(&self, input: &str) -> Option<&str> {
+        let partial = input
+            .rsplit(|c: char| c == ',' || c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('#')
+            .to_lowercase();
+        if partial.is_empty() {
+            return None;
+        }
+
+        self.store
+            .tags()
+            .iter()
+            .find(|tag| tag.starts_with(&partial) && tag.len() > partial.len())
+            .map(|tag| &tag[partial.len()..])
+    }
+
+    /// Restricts the table to todos carrying any of `tags`; an empty list shows everything
+    pub fn set_tag_filter(&mut self, tags: Vec<String>) {
+        self.query.tags = tags;
+        self.sort_todos();
+        self.clamp_selection();
+    }
+
+    pub fn set_filter(&mut self, filter: Option<Filter>) {
+        self.query.filter = filter;
+        self.active_view = None;
+        self.sort_todos();
+        self.clamp_selection();
+    }
+
+    pub fn find_view(&self, name: &str) -> Option<usize> {
+        let name = name.trim();
+        self.views
+            .iter()
+            .position(|v| v.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The active view, if it is one that was saved rather than built in
+    pub fn active_saved_view(&self) -> Option<&View> {
+        let name = self.active_view.as_ref()?;
+        self.find_view(name)
+            .map(|i| &self.views[i])
+            .filter(|v| !v.is_builtin())
+    }
+
+    /// Switches to the filter and sort of the view at `index`, remembering it
+    /// as the view to start with next time
+    pub fn apply_view(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.show_view(index)?;
+        if let Some(view) = self.views.get(index) {
+            self.store.set_setting(LAST_VIEW_SETTING, &view.name)?;
+        }
+        Ok(())
+    }
+
+    /// Switches to the filter and sort of the view at `index` without
+    /// remembering it
+    pub fn show_view(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(view) = self.views.get(index) else {
+            return Ok(());
+        };
+
+        self.query.filter = if view.filter.trim().is_empty() {
+            None
+        } else {
+            Some(Filter::parse(&view.filter)?)
+        };
+        self.sort = view.sort.clone();
+        self.active_view = Some(view.name.clone());
+
+        self.sort_todos();
+        self.clamp_selection();
+        Ok(())
+    }
+
+    /// Applies the view that was active when the app was last used, if any
+    pub fn restore_last_view(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let name = self.store.setting(LAST_VIEW_SETTING)?;
+        match name.and_then(|name| self.find_view(&name)) {
+            Some(index) => self.show_view(index),
+            None => Ok(()),
+        }
+    }
+
+    /// Saves the current filter and sort as a view called `name`, replacing
+    /// a saved view of the same name
+    pub fn save_view(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("View name cannot be empty".into());
+        }
+        if let Some(existing) = self.find_view(name).map(|i| &self.views[i]) {
+            if existing.is_builtin() {
+                return Err(format!("'{}' is a built-in view", existing.name).into());
+            }
+        }
+
+        let mut view = View {
+            id: None,
+            name: name.to_string(),
+            filter: self
+                .query
+                .filter
+                .as_ref()
+                .map(|f| f.source().to_string())
+                .unwrap_or_default(),
+            sort: self.sort.clone(),
+        };
+        view.id = Some(self.store.save_view(&view)?);
+        self.store.set_setting(LAST_VIEW_SETTING, name)?;
+
+        match self.find_view(name) {
+            Some(index) => self.views[index] = view,
+            None => self.views.push(view),
+        }
+        self.active_view = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Deletes the saved view at `index`; built-in views can't be deleted
+    pub fn delete_view(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(view) = self.views.get(index) else {
+            return Ok(());
+        };
+        let Some(id) = view.id else {
+            return Err(format!("'{}' is a built-in view", view.name).into());
+        };
+
+        self.store.delete_view(id)?;
+        let view = self.views.remove(index);
+        if self.active_view.as_ref() == Some(&view.name) {
+            self.active_view = None;
+        }
+
+        let selected = self
+            .views_state
+            .selected()
+            .map(|i| i.min(self.views.len() - 1));
+        self.views_state.select(selected);
+        Ok(())
+    }
+
+    pub fn open_views(&mut self) {
+        let active = self
+            .active_view
+            .as_ref()
+            .and_then(|name| self.find_view(name));
+        self.views_state.select(Some(active.unwrap_or(0)));
+        self.mode = Mode::Views;
+    }
+
+    pub fn close_views(&mut self) {
+        self.mode = Mode::Normal;
+    }
+
+    pub fn select_next_view(&mut self) {
+        let i = match self.views_state.selected() {
+            Some(i) if i + 1 < self.views.len() => i + 1,
+            _ => 0,
+        };
+        self.views_state.select(Some(i));
+    }
+
+    pub fn select_previous_view(&mut self) {
+        let i = match self.views_state.selected() {
+            Some(i) if i > 0 => i - 1,
+            _ => self.views.len().saturating_sub(1),
+        };
+        self.views_state.select(Some(i));
+    }
+
+    pub fn active_project(&self) -> Option<&Project> {
+        self.query
+            .project
+            .and_then(|id| self.store.projects().iter().find(|p| p.id == id))
+    }
+
+    /// Shows only the todos of the given project, or of every project for `None`
+    pub fn set_active_project(&mut self, id: Option<i64>) {
+        self.query.project = id;
+        self.sort_todos();
+        self.clamp_selection();
+    }
+
+    /// Switches to the next project tab, wrapping around. Goes backwards if `forward` is false.
+    pub fn cycle_project(&mut self, forward: bool) {
+        let projects = self.store.projects();
+        if projects.is_empty() {
+            return;
+        }
+
+        let current = self
+            .query
+            .project
+            .and_then(|id| projects.iter().position(|p| p.id == id))
+            .unwrap_or(0);
+        let len = projects.len();
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+
+        self.set_active_project(Some(projects[next].id));
+    }
+
+    pub fn find_project(&self, name: &str) -> Option<&Project> {
+        self.store.find_project(name)
+    }
+
+    pub fn create_project(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let id = self.store.create_project(name)?;
+        self.set_active_project(Some(id));
+        Ok(())
+    }
+
+    pub fn rename_active_project(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(id) = self.query.project {
+            self.store.rename_project(id, name)?;
+        }
+        Ok(())
+    }
+
+    pub fn archive_active_project(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(id) = self.query.project {
+            self.store.archive_project(id)?;
+            self.set_active_project(Some(DEFAULT_PROJECT_ID));
+        }
+        Ok(())
+    }
+
+    /// Moves the todo at `index` into the project called `name`
+    pub fn move_todo_to_project(
         &mut self,
-        text: String,
-        priority: Priority,
+        index: usize,
+        name: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let mut todo = Todo::new(text);
-        todo.priority = Some(priority);
-
-        match self.db.insert_todo(&todo) {
-            Ok(id) => {
-                todo.id = id;
-                self.todos.push(todo);
-                Ok(())
+        let project_id = self
+            .find_project(name)
+            .map(|p| p.id)
+            .ok_or_else(|| format!("No project called '{}'", name.trim()))?;
+
+        if let Some(id) = self.store.todos().get(index).map(|t| t.id) {
+            let result = self.store_mut().move_to_project(id, project_id);
+            self.changed();
+            result?;
+        }
+        Ok(())
+    }
+
+    fn update_visible(&mut self) {
+        let todos = self.store.todos();
+        let context = self.store.filter_context();
+        let matches: Vec<bool> = todos
+            .iter()
+            .map(|todo| self.query.matches(todo, &context))
+            .collect();
+
+        // children of each todo, in the current sort order
+        let mut children: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (i, todo) in todos.iter().enumerate() {
+            if let Some(parent_id) = todo.parent_id {
+                children.entry(parent_id).or_default().push(i);
             }
-            Err(e) => {
-                self.set_error(format!("Failed to add todo: {}", e));
-                Err(Box::new(e))
+        }
+
+        self.progress = children
+            .iter()
+            .map(|(&parent_id, indexes)| {
+                let done = indexes.iter().filter(|&&i| todos[i].completed).count();
+                (parent_id, (done, indexes.len()))
+            })
+            .collect();
+
+        // a todo is shown at the top level if its parent isn't shown, e.g.
+        // because the parent was filtered out or deleted
+        let shown: HashSet<i64> = todos
+            .iter()
+            .zip(&matches)
+            .filter(|(_, &matches)| matches)
+            .map(|(todo, _)| todo.id)
+            .collect();
+
+        self.visible.clear();
+        self.depths.clear();
+        let mut seen = HashSet::new();
+        let mut pending: Vec<(usize, usize)> = todos
+            .iter()
+            .enumerate()
+            .filter(|&(i, todo)| matches[i] && todo.parent_id.is_none_or(|p| !shown.contains(&p)))
+            .map(|(i, _)| (i, 0))
+            .rev()
+            .collect();
+
+        while let Some((index, depth)) = pending.pop() {
+            // guard against cycles in corrupted data
+            if !seen.insert(index) {
+                continue;
+            }
+            self.visible.push(index);
+            self.depths.push(depth);
+
+            let id = todos[index].id;
+            if self.collapsed.contains(&id) {
+                continue;
+            }
+            if let Some(indexes) = children.get(&id) {
+                pending.extend(
+                    indexes
+                        .iter()
+                        .rev()
+                        .filter(|&&i| matches[i])
+                        .map(|&i| (i, depth + 1)),
+                );
             }
         }
     }
 
-    pub fn toggle_todo(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(todo) = self.todos.get_mut(index) {
-            todo.completed = !todo.completed;
-            todo.completed_at = if todo.completed {
-                Some(Local::now())
-            } else {
-                None
-            };
+    /// How many of a todo's direct subtasks are done, as `(done, total)`
+    pub fn subtask_progress(&self, id: i64) -> Option<(usize, usize)> {
+        self.progress.get(&id).copied()
+    }
+
+    pub fn is_collapsed(&self, id: i64) -> bool {
+        self.collapsed.contains(&id)
+    }
+
+    /// Hides the subtasks of the selected todo, or selects its parent if
+    /// there is nothing to hide
+    pub fn collapse_selected(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let todo = &self.store.todos()[index];
+
+        if self.progress.contains_key(&todo.id) && !self.collapsed.contains(&todo.id) {
+            self.collapsed.insert(todo.id);
+            self.update_visible();
+        } else if let Some(parent_id) = todo.parent_id {
+            self.select_by_id(Some(parent_id));
+        }
+    }
+
+    /// Shows the subtasks of the selected todo
+    pub fn expand_selected(&mut self) {
+        if let Some(id) = self.selected_id() {
+            if self.collapsed.remove(&id) {
+                self.update_visible();
+            }
+        }
+    }
 
-            self.db.update_todo(todo)?;
+    pub fn toggle_collapsed(&mut self) {
+        match self.selected_id() {
+            Some(id) if self.collapsed.contains(&id) => self.expand_selected(),
+            Some(_) => self.collapse_selected(),
+            None => {}
         }
+    }
+
+    pub fn open_trash(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.trash = self.store.deleted()?;
+        self.trash_state
+            .select(if self.trash.is_empty() { None } else { Some(0) });
+        self.mode = Mode::Trash;
         Ok(())
     }
 
-    pub fn delete_todo(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
-        if index < self.todos.len() {
-            let todo = &self.todos[index];
-            self.db.delete_todo(todo.id)?;
-            self.todos.remove(index);
+    pub fn close_trash(&mut self) {
+        self.trash.clear();
+        self.mode = Mode::Normal;
+    }
+
+    /// Moves a todo from the trash back into the active list
+    pub fn restore_from_trash(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if index < self.trash.len() {
+            let id = self.trash[index].id;
+            self.store.set_focus(Some(id));
+            self.store.restore(id)?;
+            // subtasks trashed with it come back too
+            self.trash = self.store.deleted()?;
+            self.sort_todos();
+            self.clamp_trash_selection();
         }
         Ok(())
     }
 
-    pub fn clear_completed(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        for todo in self.todos.iter().filter(|t| t.completed) {
-            self.db.delete_todo(todo.id)?;
+    /// Permanently deletes a todo from the trash
+    pub fn purge_from_trash(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if index < self.trash.len() {
+            self.store.purge(self.trash[index].id)?;
+            // subtasks in the trash go with it, and any restored ones are detached
+            self.trash = self.store.deleted()?;
+            self.sort_todos();
+            self.clamp_trash_selection();
+        }
+        Ok(())
+    }
+
+    /// Permanently deletes everything that has been in the trash for more than `days` days
+    pub fn purge_trash_older_than(
+        &mut self,
+        days: u64,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let purged = self.store.purge_older_than(days)?;
+        self.trash = self.store.deleted()?;
+        self.clamp_trash_selection();
+        Ok(purged)
+    }
+
+    fn clamp_trash_selection(&mut self) {
+        let selected = match self.trash_state.selected() {
+            _ if self.trash.is_empty() => None,
+            Some(i) => Some(i.min(self.trash.len() - 1)),
+            None => Some(0),
+        };
+        self.trash_state.select(selected);
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(PromptState {
+            kind,
+            input: String::new(),
+        });
+        self.mode = Mode::Prompt;
+    }
+
+    /// Closes the prompt, returning it so the caller can act on the input
+    pub fn close_prompt(&mut self) -> Option<PromptState> {
+        let prompt = self.prompt.take();
+        self.mode = prompt
+            .as_ref()
+            .map(|p| p.kind.return_mode())
+            .unwrap_or(Mode::Normal);
+        prompt
+    }
+
+    pub fn open_search(&mut self) {
+        self.search = Some(SearchState::new(self.table_state.selected()));
+        self.mode = Mode::Search;
+    }
+
+    /// Ends the search, going back to the row selected before it started if
+    /// `restore` is set
+    pub fn close_search(&mut self, restore: bool) {
+        if let Some(search) = self.search.take() {
+            if restore {
+                self.table_state.select(search.origin);
+            }
+        }
+        self.mode = Mode::Normal;
+    }
+
+    /// Recompiles the query and jumps to the first match at or after the row
+    /// the search started from
+    pub fn update_search(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.update();
+        let origin = search.origin;
+
+        let rows = self.search_matches();
+        let start = origin.unwrap_or(0);
+        match rows.iter().find(|&&row| row >= start).or(rows.first()) {
+            Some(&row) => self.table_state.select(Some(row)),
+            None => self.table_state.select(origin),
+        }
+    }
+
+    /// Selects the next (or previous) matching row, wrapping around the list
+    pub fn step_search(&mut self, forward: bool) {
+        let rows = self.search_matches();
+        let current = self.table_state.selected();
+
+        let next = if forward {
+            rows.iter()
+                .find(|&&row| current.is_none_or(|c| row > c))
+                .or(rows.first())
+        } else {
+            rows.iter()
+                .rev()
+                .find(|&&row| current.is_none_or(|c| row < c))
+                .or(rows.last())
+        };
+
+        match next {
+            Some(&row) => self.table_state.select(Some(row)),
+            None => self.set_error(String::from("No matches")),
+        }
+    }
+
+    /// Table rows whose todo text matches the current search
+    pub fn search_matches(&self) -> Vec<usize> {
+        let Some(search) = &self.search else {
+            return Vec::new();
+        };
+        self.visible
+            .iter()
+            .enumerate()
+            .filter(|(_, &i)| search.is_match(&self.store.todos()[i].text))
+            .map(|(row, _)| row)
+            .collect()
+    }
+
+    /// Full-text search of the text and notes of every todo in the database
+    pub fn search_all(
+        &self,
+        query: &str,
+        include_archived: bool,
+    ) -> Result<Vec<SearchHit>, Box<dyn std::error::Error>> {
+        Ok(self.store.search(query, include_archived)?)
+    }
+
+    /// Runs a full-text search of the database and shows the results
+    pub fn run_full_text_search(
+        &mut self,
+        query: &str,
+        include_archived: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let hits = self.search_all(query, include_archived)?;
+        let mut state = TableState::default();
+        state.select(if hits.is_empty() { None } else { Some(0) });
+
+        self.results = Some(SearchResults {
+            query: query.to_string(),
+            include_archived,
+            hits,
+            state,
+        });
+        self.mode = Mode::SearchResults;
+        Ok(())
+    }
+
+    /// Runs the current full-text search again, flipping whether deleted and
+    /// archived todos are included
+    pub fn toggle_results_archived(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(results) = self.results.take() {
+            self.run_full_text_search(&results.query, !results.include_archived)?;
         }
-        self.todos.retain(|todo| !todo.completed);
         Ok(())
     }
 
+    pub fn close_results(&mut self) {
+        self.results = None;
+        self.mode = Mode::Normal;
+    }
+
+    pub fn select_next_result(&mut self) {
+        if let Some(results) = self.results.as_mut() {
+            let i = match results.state.selected() {
+                Some(i) if i + 1 < results.hits.len() => i + 1,
+                _ => 0,
+            };
+            results.state.select(Some(i));
+        }
+    }
+
+    pub fn select_previous_result(&mut self) {
+        if let Some(results) = self.results.as_mut() {
+            let i = match results.state.selected() {
+                Some(i) if i > 0 => i - 1,
+                _ => results.hits.len().saturating_sub(1),
+            };
+            results.state.select(Some(i));
+        }
+    }
+
+    /// Closes the results and shows the selected todo where it lives: in the
+    /// trash if it was deleted, otherwise in the todo list
+    pub fn open_selected_result(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(results) = &self.results else {
+            return Ok(());
+        };
+        let Some(hit) = results.state.selected().and_then(|i| results.hits.get(i)) else {
+            return Ok(());
+        };
+        let (id, project_id, deleted) = (
+            hit.todo.id,
+            hit.todo.project_id,
+            hit.todo.deleted_at.is_some(),
+        );
+
+        if !deleted && !self.store.projects().iter().any(|p| p.id == project_id) {
+            return Err("That todo is in an archived project".into());
+        }
+
+        self.close_results();
+        if deleted {
+            self.open_trash()?;
+            let row = self.trash.iter().position(|t| t.id == id);
+            self.trash_state.select(row.or(self.trash_state.selected()));
+        } else {
+            self.reveal_todo(id);
+        }
+        Ok(())
+    }
+
+    /// Selects a todo, switching project, expanding its ancestors and
+    /// clearing the tag filter as needed to make it visible
+    fn reveal_todo(&mut self, id: i64) {
+        let Some(todo) = self.store.get(id) else {
+            return;
+        };
+
+        let (project_id, mut parent) = (todo.project_id, todo.parent_id);
+        if self.query.project.is_some_and(|p| p != project_id) {
+            self.query.project = Some(project_id);
+        }
+
+        while let Some(parent_id) = parent {
+            self.collapsed.remove(&parent_id);
+            parent = self.store.get(parent_id).and_then(|t| t.parent_id);
+        }
+
+        self.sort_todos();
+        if !self
+            .index_of(id)
+            .is_some_and(|index| self.visible.contains(&index))
+        {
+            self.query.tags.clear();
+            self.query.filter = None;
+        }
+        self.select_by_id(Some(id));
+    }
+
     pub fn update_todo(
         &mut self,
         index: usize,
-        text: String,
-        priority: Priority,
+        fields: TodoFields,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(todo) = self.todos.get_mut(index) {
-            todo.text = text;
-            todo.priority = Some(priority);
-            self.db.update_todo(todo)?;
+        if let Some(id) = self.store.todos().get(index).map(|t| t.id) {
+            let result = self.store_mut().update(id, fields);
+            self.sort_todos();
+            result?;
         }
         Ok(())
     }
 
     pub fn sort_todos(&mut self) {
-        match self.sort_by {
-            SortCriteria::Priority => self.todos.sort_by(|a, b| {
-                let ordering = a.priority.unwrap().cmp(&b.priority.unwrap());
-                if self.sort_asc {
-                    ordering
-                } else {
-                    ordering.reverse()
-                }
-            }),
-            SortCriteria::Completed => self.todos.sort_by(|a, b| {
-                let ordering = a.completed.cmp(&b.completed);
-                if self.sort_asc {
-                    ordering
-                } else {
-                    ordering.reverse()
-                }
-            }),
-            SortCriteria::CreatedDate => self.todos.sort_by(|a, b| {
-                let ordering = a.created_at.cmp(&b.created_at);
-                if self.sort_asc {
-                    ordering
-                } else {
-                    ordering.reverse()
-                }
-            }),
+        self.store.sort(&self.sort);
+
+        self.update_visible();
+    }
+
+    /// Reverses the direction of the first sort key
+    pub fn toggle_sort_direction(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.sort.reverse_first();
+        self.sort_changed()
+    }
+
+    /// Sorts by `criteria` first, ties broken by the keys sorted by before.
+    /// Picking the first key again reverses its direction.
+    pub fn set_sort_criteria(
+        &mut self,
+        criteria: SortCriteria,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.sort.promote(criteria);
+        self.sort_changed()
+    }
+
+    pub fn set_sort(&mut self, sort: SortSpec) -> Result<(), Box<dyn std::error::Error>> {
+        self.sort = sort;
+        self.sort_changed()
+    }
+
+    /// Moves the selected todo one place up or down among its siblings when
+    /// sorted by hand. Usually only the moved todo's position changes; when
+    /// there's no room left between its new neighbours every todo is
+    /// renumbered first.
+    pub fn move_selected(&mut self, up: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(first) = self.sort.keys().first().copied() else {
+            return Ok(());
+        };
+        if first.criteria != SortCriteria::Manual {
+            return Err("Sort by manual order (M) to move todos".into());
         }
+        let Some(row) = self.table_state.selected() else {
+            return Ok(());
+        };
+
+        let siblings = self.sibling_rows(row);
+        let Some(at) = siblings.iter().position(|&r| r == row) else {
+            return Ok(());
+        };
+        // the ids of the siblings the todo ends up between
+        let id_at = |i: usize| self.store.todos()[self.visible[siblings[i]]].id;
+        let (before, after) = if up && at > 0 {
+            (at.checked_sub(2).map(id_at), Some(id_at(at - 1)))
+        } else if !up && at + 1 < siblings.len() {
+            (
+                Some(id_at(at + 1)),
+                (at + 2 < siblings.len()).then(|| id_at(at + 2)),
+            )
+        } else {
+            return Ok(());
+        };
+
+        let position = match self.position_between(before, after, first.ascending) {
+            Some(position) => position,
+            None => {
+                self.renumber_positions()?;
+                self.position_between(before, after, first.ascending)
+                    .ok_or("No room to move the todo")?
+            }
+        };
+
+        let id = self.store.todos()[self.visible[row]].id;
+        self.store.set_focus(Some(id));
+        self.store.set_position(id, position)?;
+        self.select_by_id(Some(id));
+        Ok(())
     }
 
-    pub fn toggle_sort_direction(&mut self) {
-        self.sort_asc = !self.sort_asc;
+    // the rows of the todos shown at the same level as `row` under the same
+    // parent, in the order they're shown
+    fn sibling_rows(&self, row: usize) -> Vec<usize> {
+        let depth = self.depths[row];
+        let same_level = |rows: &mut dyn Iterator<Item = usize>| -> Vec<usize> {
+            rows.take_while(|&r| self.depths[r] >= depth)
+                .filter(|&r| self.depths[r] == depth)
+                .collect()
+        };
+
+        let mut siblings = same_level(&mut (0..row).rev());
+        siblings.reverse();
+        siblings.push(row);
+        siblings.extend(same_level(&mut (row + 1..self.visible.len())));
+        siblings
     }
 
-    pub fn set_sort_criteria(&mut self, criteria: SortCriteria) {
-        if self.sort_by == criteria {
-            self.toggle_sort_direction();
-            self.sort_todos();
+    // a position that shows a todo between the todos with ids `before` and
+    // `after`, or `None` if their positions are too close together
+    fn position_between(
+        &self,
+        before: Option<i64>,
+        after: Option<i64>,
+        ascending: bool,
+    ) -> Option<i64> {
+        // positions as they're laid out on screen, increasing downwards
+        let shown = |id: i64| {
+            let position = self.store.get(id)?.position;
+            Some(if ascending { position } else { -position })
+        };
+
+        let shown_position = match (before.and_then(shown), after.and_then(shown)) {
+            (Some(before), Some(after)) if after - before >= 2 => before + (after - before) / 2,
+            (Some(_), Some(_)) => return None,
+            (Some(before), None) => before + POSITION_GAP,
+            (None, Some(after)) => after - POSITION_GAP,
+            (None, None) => return None,
+        };
+        Some(if ascending {
+            shown_position
         } else {
-            self.sort_by = criteria;
-            self.sort_asc = true;
+            -shown_position
+        })
+    }
+
+    fn renumber_positions(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let positions = self.store.renumber_positions()?;
+        for todo in self.trash.iter_mut() {
+            if let Some(&position) = positions.get(&todo.id) {
+                todo.position = position;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn theme_name(&self) -> &str {
+        &self.config.themes[self.theme].name
+    }
+
+    /// Switches to the next theme, remembering it for the next session
+    pub fn next_theme(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_theme((self.theme + 1) % self.config.themes.len());
+        self.store
+            .set_setting(THEME_SETTING, &self.config.themes[self.theme].name)?;
+        Ok(())
+    }
+
+    /// Switches to the theme picked when the app was last used, if it still exists
+    pub fn restore_theme(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let name = self.store.setting(THEME_SETTING)?;
+        if let Some(index) = name.and_then(|name| self.config.find_theme(&name)) {
+            self.set_theme(index);
+        }
+        Ok(())
+    }
+
+    fn set_theme(&mut self, index: usize) {
+        self.theme = index;
+        self.colors = self.config.themes[index]
+            .colors
+            .clone()
+            .adapt(self.color_support);
+    }
+
+    /// Applies the sort used when the app was last closed, if one was saved
+    pub fn restore_sort(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(sort) = self.store.setting(SORT_SETTING)? {
+            self.sort = sort.parse()?;
             self.sort_todos();
         }
+        Ok(())
+    }
+
+    // re-sorts after the sort was changed by hand and remembers it for the
+    // next session, instead of whichever view was active
+    fn sort_changed(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.active_view = None;
+        self.sort_todos();
+        self.store
+            .set_setting(SORT_SETTING, &self.sort.to_string())?;
+        self.store.delete_setting(LAST_VIEW_SETTING)?;
+        Ok(())
+    }
+
+    pub fn select_next_deleted(&mut self) {
+        let i = match self.trash_state.selected() {
+            Some(i) if i + 1 < self.trash.len() => i + 1,
+            _ => 0,
+        };
+        self.trash_state.select(Some(i));
+    }
+
+    pub fn select_previous_deleted(&mut self) {
+        let i = match self.trash_state.selected() {
+            Some(i) if i > 0 => i - 1,
+            _ => self.trash.len().saturating_sub(1),
+        };
+        self.trash_state.select(Some(i));
+    }
+
+    /// Selects the todo shown in `row`, if there is one
+    pub fn select_row(&mut self, row: usize) {
+        if row < self.visible.len() {
+            self.table_state.select(Some(row));
+        }
     }
 
     pub fn select_next(&mut self) {
         let i = match self.table_state.selected() {
             Some(i) => {
-                if i >= self.todos.len().saturating_sub(1) {
+                if i >= self.visible.len().saturating_sub(1) {
                     0
                 } else {
                     i + 1
@@ -225,7 +1233,7 @@ impl App {
         let i = match self.table_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.todos.len().saturating_sub(1)
+                    self.visible.len().saturating_sub(1)
                 } else {
                     i - 1
                 }
@@ -0,0 +1,553 @@
+use std::{
+    io::{self, ErrorKind, Write},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Local};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::{
+    app::{
+        action::{Action, Effect},
+        state::App,
+    },
+    models::filter::Filter,
+    models::todo::{
+        format_due, parse_due, parse_tags, Priority, SearchHit, Todo, JSON_FORMAT_VERSION,
+    },
+    store::todo_store::TodoFields,
+};
+
+/// A minimalist TUI todo application. Run without a command to start the TUI.
+#[derive(Parser)]
+#[command(name = "r_todo", version, about)]
+pub struct Cli {
+    /// Read the config from this file instead of config.toml in the config directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Add a new todo
+    Add {
+        /// The todo text
+        text: String,
+        /// high, medium or low; defaults to `default_priority` from the config
+        #[arg(short, long)]
+        priority: Option<Priority>,
+        /// Due date as YYYY-MM-DD [HH:MM], today or tomorrow
+        #[arg(short, long)]
+        due: Option<String>,
+        /// Comma separated tags
+        #[arg(short = 'g', long)]
+        tags: Option<String>,
+        /// Name of the project to add the todo to, defaults to the first project
+        #[arg(short = 'P', long)]
+        project: Option<String>,
+        /// Id of the todo to add this as a subtask of
+        #[arg(long, conflicts_with = "project")]
+        parent: Option<i64>,
+        /// Free-form notes
+        #[arg(short, long)]
+        notes: Option<String>,
+    },
+    /// List todos
+    List {
+        /// Only show todos that are not completed
+        #[arg(short, long)]
+        open: bool,
+        /// Only show todos with any of these comma separated tags
+        #[arg(short = 'g', long)]
+        tags: Option<String>,
+        /// Only show todos in this project
+        #[arg(short = 'P', long)]
+        project: Option<String>,
+        /// Only show todos matching a filter query, e.g. 'priority:high -done tag:infra'
+        #[arg(short = 'F', long)]
+        filter: Option<String>,
+        /// Use the filter and sort of a built-in or saved view
+        #[arg(long, conflicts_with = "filter")]
+        view: Option<String>,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Full-text search of todo text and notes, best matches first
+    Search {
+        /// Words to search for; each matches words starting with it
+        query: String,
+        /// Also search deleted todos and todos in archived projects
+        #[arg(short, long)]
+        all: bool,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Show a single todo
+    Show {
+        /// The todo id, as shown by `list`
+        id: i64,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Mark a todo as completed
+    Done {
+        /// The todo id, as shown by `list`
+        id: i64,
+    },
+    /// Change the text, priority, due date, tags or notes of a todo
+    Edit {
+        /// The todo id, as shown by `list`
+        id: i64,
+        /// New todo text
+        #[arg(short, long)]
+        text: Option<String>,
+        /// New priority: high, medium or low
+        #[arg(short, long)]
+        priority: Option<Priority>,
+        /// New due date as YYYY-MM-DD [HH:MM]; an empty string clears it
+        #[arg(short, long)]
+        due: Option<String>,
+        /// New comma separated tags; an empty string clears them
+        #[arg(short = 'g', long)]
+        tags: Option<String>,
+        /// New notes; an empty string clears them
+        #[arg(short, long)]
+        notes: Option<String>,
+    },
+    /// Move a todo to the trash
+    Rm {
+        /// The todo id, as shown by `list`
+        id: i64,
+    },
+    /// Print the configuration in use, with every default filled in
+    Config,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable columns
+    Text,
+    /// A single JSON document: `{"version": 1, "todos": [...]}`
+    Json,
+    /// One JSON object per line, each carrying its own `version`
+    Ndjson,
+}
+
+/// A todo as the JSON formats write it. Kept apart from `Todo` so storage
+/// details stay out and the format only changes on purpose, along with
+/// `JSON_FORMAT_VERSION`.
+#[derive(Serialize)]
+struct TodoJson<'a> {
+    id: i64,
+    text: &'a str,
+    priority: Option<Priority>,
+    completed: bool,
+    created_at: DateTime<Local>,
+    completed_at: Option<DateTime<Local>>,
+    /// `YYYY-MM-DD`, followed by ` HH:MM` if there's a time
+    due: Option<String>,
+    tags: &'a [String],
+    /// id of the parent todo of a subtask
+    parent: Option<i64>,
+    notes: &'a str,
+}
+
+impl<'a> From<&'a Todo> for TodoJson<'a> {
+    fn from(todo: &'a Todo) -> Self {
+        TodoJson {
+            id: todo.id,
+            text: &todo.text,
+            priority: todo.priority,
+            completed: todo.completed,
+            created_at: todo.created_at,
+            completed_at: todo.completed_at,
+            due: todo
+                .due_date
+                .map(|_| format_due(todo.due_date, todo.due_time)),
+            tags: &todo.tags,
+            parent: todo.parent_id,
+            notes: &todo.notes,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SearchHitJson<'a> {
+    #[serde(flatten)]
+    todo: TodoJson<'a>,
+    snippet: &'a str,
+    rank: f64,
+}
+
+impl<'a> From<&'a SearchHit> for SearchHitJson<'a> {
+    fn from(hit: &'a SearchHit) -> Self {
+        SearchHitJson {
+            todo: TodoJson::from(&hit.todo),
+            snippet: &hit.snippet,
+            rank: hit.rank,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TodoList<'a> {
+    version: u32,
+    todos: Vec<TodoJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct SearchList<'a> {
+    version: u32,
+    results: Vec<SearchHitJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct SearchRecord<'a> {
+    version: u32,
+    #[serde(flatten)]
+    hit: SearchHitJson<'a>,
+}
+
+#[derive(Serialize)]
+struct TodoRecord<'a> {
+    version: u32,
+    #[serde(flatten)]
+    todo: TodoJson<'a>,
+}
+
+pub fn run(command: Command, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        // printed by main before the database is opened, so it works even
+        // when the configured database can't be
+        Command::Config => unreachable!("handled in main before the database is opened"),
+        Command::Add {
+            text,
+            priority,
+            due,
+            tags,
+            project,
+            parent,
+            notes,
+        } => {
+            if let Some(project) = project {
+                let id = find_project(app, &project)?;
+                app.set_active_project(Some(id));
+            }
+
+            let (due_date, due_time) = parse_due(due.as_deref().unwrap_or_default())?;
+            let fields = TodoFields {
+                text,
+                priority: Some(priority.unwrap_or(app.config.default_priority)),
+                due_date,
+                due_time,
+                tags: parse_tags(tags.as_deref().unwrap_or_default()),
+                notes: notes.unwrap_or_default(),
+            };
+            let action = Action::AddTodo {
+                fields,
+                parent_id: parent,
+            };
+            if let Effect::Added(id) = app.handle(action)? {
+                println!("Added todo {}", id);
+            }
+        }
+        Command::List {
+            open,
+            tags,
+            project,
+            filter,
+            view,
+            format,
+        } => {
+            let project_id = match project {
+                Some(project) => Some(find_project(app, &project)?),
+                None => None,
+            };
+            app.set_active_project(project_id);
+            app.set_tag_filter(parse_tags(tags.as_deref().unwrap_or_default()));
+            if let Some(filter) = filter {
+                app.set_filter(Some(Filter::parse(&filter)?));
+            }
+            if let Some(view) = view {
+                let index = app
+                    .find_view(&view)
+                    .ok_or_else(|| format!("No view named '{}'", view))?;
+                app.show_view(index)?;
+            }
+            let rows: Vec<(&Todo, usize)> = app
+                .visible
+                .iter()
+                .zip(&app.depths)
+                .map(|(&i, &depth)| (&app.store.todos()[i], depth))
+                .filter(|(t, _)| !open || !t.completed)
+                .collect();
+            ignore_broken_pipe(print_todos(&mut io::stdout().lock(), &rows, format))?;
+        }
+        Command::Search { query, all, format } => {
+            let hits = app.search_all(&query, all)?;
+            ignore_broken_pipe(print_search_hits(&mut io::stdout().lock(), &hits, format))?;
+        }
+        Command::Show { id, format } => {
+            let todo = &app.store.todos()[find_todo(app, id)?];
+            ignore_broken_pipe(print_todo(&mut io::stdout().lock(), todo, format))?;
+        }
+        Command::Done { id } => {
+            app.handle(Action::CompleteTodo(id))?;
+            println!("Completed todo {}", id);
+        }
+        Command::Edit {
+            id,
+            text,
+            priority,
+            due,
+            tags,
+            notes,
+        } => {
+            let todo = &app.store.todos()[find_todo(app, id)?];
+
+            let (due_date, due_time) = match due {
+                Some(due) => parse_due(&due)?,
+                None => (todo.due_date, todo.due_time),
+            };
+
+            let fields = TodoFields {
+                text: text.unwrap_or_else(|| todo.text.clone()),
+                priority: priority.or(todo.priority),
+                due_date,
+                due_time,
+                tags: match tags {
+                    Some(tags) => parse_tags(&tags),
+                    None => todo.tags.clone(),
+                },
+                notes: notes.unwrap_or_else(|| todo.notes.clone()),
+            };
+
+            app.handle(Action::UpdateTodo { id, fields })?;
+            println!("Updated todo {}", id);
+        }
+        Command::Rm { id } => {
+            app.handle(Action::DeleteTodo(id))?;
+            println!("Moved todo {} to the trash", id);
+        }
+    }
+
+    Ok(())
+}
+
+fn find_todo(app: &App, id: i64) -> Result<usize, String> {
+    app.index_of(id)
+        .ok_or_else(|| format!("No todo with id {}", id))
+}
+
+fn find_project(app: &App, name: &str) -> Result<i64, String> {
+    app.find_project(name)
+        .map(|p| p.id)
+        .ok_or_else(|| format!("No project called '{}'", name))
+}
+
+fn record(todo: &Todo) -> TodoRecord<'_> {
+    TodoRecord {
+        version: JSON_FORMAT_VERSION,
+        todo: TodoJson::from(todo),
+    }
+}
+
+// a reader that goes away early, as in `r_todo list | head`, isn't an error
+fn ignore_broken_pipe(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Prints todos along with their nesting depth, which only the text format shows
+fn print_todos(
+    out: &mut impl Write,
+    rows: &[(&Todo, usize)],
+    format: OutputFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for (todo, depth) in rows {
+                writeln!(out, "{}", format_todo_line(todo, *depth))?;
+            }
+        }
+        OutputFormat::Json => {
+            let list = TodoList {
+                version: JSON_FORMAT_VERSION,
+                todos: rows.iter().map(|(todo, _)| TodoJson::from(*todo)).collect(),
+            };
+            writeln!(out, "{}", serde_json::to_string_pretty(&list)?)?;
+        }
+        OutputFormat::Ndjson => {
+            for (todo, _) in rows {
+                writeln!(out, "{}", serde_json::to_string(&record(todo))?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_search_hits(
+    out: &mut impl Write,
+    hits: &[SearchHit],
+    format: OutputFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for hit in hits {
+                let deleted = if hit.todo.deleted_at.is_some() {
+                    " (deleted)"
+                } else {
+                    ""
+                };
+                writeln!(out, "{}{}", format_todo_line(&hit.todo, 0), deleted)?;
+                writeln!(out, "       {}", highlight_snippet(hit))?;
+            }
+        }
+        OutputFormat::Json => {
+            let list = SearchList {
+                version: JSON_FORMAT_VERSION,
+                results: hits.iter().map(SearchHitJson::from).collect(),
+            };
+            writeln!(out, "{}", serde_json::to_string_pretty(&list)?)?;
+        }
+        OutputFormat::Ndjson => {
+            for hit in hits {
+                let record = SearchRecord {
+                    version: JSON_FORMAT_VERSION,
+                    hit: SearchHitJson::from(hit),
+                };
+                writeln!(out, "{}", serde_json::to_string(&record)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_todo(out: &mut impl Write, todo: &Todo, format: OutputFormat) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => print_todo_details(out, todo),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            writeln!(out, "{}", serde_json::to_string(&record(todo))?)
+        }
+    }
+}
+
+// the snippet on one line, with the matched words in [brackets]
+fn highlight_snippet(hit: &SearchHit) -> String {
+    let mut output = String::new();
+    let mut end = 0;
+    for range in &hit.matches {
+        output.push_str(&hit.snippet[end..range.start]);
+        output.push_str(&format!("[{}]", &hit.snippet[range.clone()]));
+        end = range.end;
+    }
+    output.push_str(&hit.snippet[end..]);
+    output.replace('\n', " ")
+}
+
+fn print_todo_details(out: &mut impl Write, todo: &Todo) -> io::Result<()> {
+    writeln!(out, "Id:        {}", todo.id)?;
+    writeln!(out, "Text:      {}", todo.text)?;
+    writeln!(
+        out,
+        "Priority:  {}",
+        todo.priority.map(|p| p.to_string()).unwrap_or_default()
+    )?;
+    writeln!(
+        out,
+        "Completed: {}",
+        if todo.completed { "yes" } else { "no" }
+    )?;
+    writeln!(
+        out,
+        "Created:   {}",
+        todo.created_at.format("%Y-%m-%d %H:%M")
+    )?;
+    if let Some(completed_at) = todo.completed_at {
+        writeln!(out, "Done at:   {}", completed_at.format("%Y-%m-%d %H:%M"))?;
+    }
+    if todo.due_date.is_some() {
+        writeln!(out, "Due:       {}", todo.due_display())?;
+    }
+    if !todo.tags.is_empty() {
+        writeln!(out, "Tags:      {}", todo.tags.join(", "))?;
+    }
+    if let Some(parent_id) = todo.parent_id {
+        writeln!(out, "Parent:    {}", parent_id)?;
+    }
+    if !todo.notes.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", todo.notes)?;
+    }
+    Ok(())
+}
+
+fn format_todo_line(todo: &Todo, depth: usize) -> String {
+    format!(
+        "{:>4} [{}] {:<6} {:<16} {}{}{}",
+        todo.id,
+        if todo.completed { "x" } else { " " },
+        todo.priority.map(|p| p.to_string()).unwrap_or_default(),
+        format_due(todo.due_date, todo.due_time),
+        "  ".repeat(depth),
+        todo.text,
+        todo.tags
+            .iter()
+            .map(|tag| format!(" #{}", tag))
+            .collect::<String>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime, TimeZone};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn json_records_have_the_documented_fields() {
+        let created_at = Local.with_ymd_and_hms(2024, 5, 1, 9, 30, 0).unwrap();
+        let todo = Todo {
+            id: 7,
+            text: "Write report".to_string(),
+            completed: false,
+            created_at,
+            completed_at: None,
+            priority: Some(Priority::High),
+            due_date: NaiveDate::from_ymd_opt(2024, 5, 3),
+            due_time: NaiveTime::from_hms_opt(17, 0, 0),
+            deleted_at: None,
+            tags: vec!["work".to_string()],
+            project_id: 2,
+            parent_id: Some(3),
+            notes: "First line\nSecond line".to_string(),
+            position: 4,
+        };
+
+        assert_eq!(
+            serde_json::to_value(record(&todo)).unwrap(),
+            json!({
+                "version": JSON_FORMAT_VERSION,
+                "id": 7,
+                "text": "Write report",
+                "priority": "high",
+                "completed": false,
+                "created_at": created_at,
+                "completed_at": null,
+                "due": "2024-05-03 17:00",
+                "tags": ["work"],
+                "parent": 3,
+                "notes": "First line\nSecond line",
+            })
+        );
+    }
+}
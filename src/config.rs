@@ -0,0 +1,259 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    app::keymap::Keymap,
+    models::{todo::Priority, view::SortSpec},
+    ui::{
+        color_scheme::{builtin_themes, ColorScheme, Theme},
+        color_support::ColorSupport,
+    },
+};
+
+const CONFIG_FILE: &str = "config.toml";
+const THEMES_DIR: &str = "themes";
+
+/// Settings read from `config.toml` in the config directory, e.g.
+/// `~/.config/r_todo/config.toml` on Linux. Every setting is optional and
+/// falls back to the value shown below:
+///
+/// ```toml
+/// # where todos are stored, defaults to todos.db in the data directory
+/// database = "/home/me/todos.db"
+/// # how often the screen is redrawn, in milliseconds (1-1000)
+/// tick_rate_ms = 20
+/// # how long error messages stay on screen, in seconds
+/// error_timeout_secs = 15
+/// # the priority new todos start with: high, medium or low
+/// default_priority = "medium"
+/// # the sort used until another one is picked
+/// default_sort = "priority"
+/// # the colours used until another theme is picked: dark, light,
+/// # high-contrast, solarized or the name of a file in the themes directory
+/// theme = "dark"
+/// # how many colours to use: auto, truecolor, 256, 16 or mono; auto
+/// # goes by NO_COLOR, COLORTERM and TERM
+/// colors = "auto"
+///
+/// # keys for each mode, see `Keymap`
+/// [keys.normal]
+/// new_todo = "n"
+/// ```
+///
+/// Theme files go in a `themes` directory next to the config file, e.g.
+/// `themes/nord.toml` for a theme called `nord`; see `ColorScheme` for
+/// their format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<PathBuf>,
+    pub tick_rate_ms: u64,
+    pub error_timeout_secs: u64,
+    pub default_priority: Priority,
+    pub default_sort: SortSpec,
+    pub theme: String,
+    pub colors: ColorSupport,
+    pub keys: Keymap,
+    /// The built-in themes followed by those loaded from theme files
+    #[serde(skip)]
+    pub themes: Vec<Theme>,
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("Invalid config in {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("Invalid config in {path}: {message}")]
+    Invalid { path: PathBuf, message: String },
+
+    #[error("Failed to determine the config directory")]
+    NoConfigDir,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database: None,
+            tick_rate_ms: 20,
+            error_timeout_secs: 15,
+            default_priority: Priority::Medium,
+            default_sort: SortSpec::default(),
+            theme: String::from("dark"),
+            colors: ColorSupport::Auto,
+            keys: Keymap::default(),
+            themes: builtin_themes(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from `path`, which must exist, or otherwise from the
+    /// config directory, where a missing file means the defaults are used.
+    /// Returns the config and the file it was read from, if any.
+    pub fn load(path: Option<&Path>) -> Result<(Config, Option<PathBuf>), ConfigError> {
+        let (path, exists) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => {
+                let path = default_path()?;
+                let exists = path.exists();
+                (path, exists)
+            }
+        };
+
+        let mut config = if exists {
+            let text = fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+                path: path.clone(),
+                source,
+            })?;
+            toml::from_str(&text).map_err(|source| ConfigError::Parse {
+                path: path.clone(),
+                source,
+            })?
+        } else {
+            Config::default()
+        };
+
+        let themes_dir = path.with_file_name(THEMES_DIR);
+        config.themes.extend(load_themes(&themes_dir)?);
+        config.validate().map_err(|message| ConfigError::Invalid {
+            path: path.clone(),
+            message,
+        })?;
+
+        Ok((config, exists.then_some(path)))
+    }
+
+    // checks the values that parse but make no sense
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=1000).contains(&self.tick_rate_ms) {
+            return Err(format!(
+                "tick_rate_ms must be between 1 and 1000, found {}",
+                self.tick_rate_ms
+            ));
+        }
+        if self.error_timeout_secs == 0 {
+            return Err(String::from("error_timeout_secs must be at least 1"));
+        }
+        if self.database.as_ref().is_some_and(|p| p.is_dir()) {
+            return Err(String::from("database must be a file, not a directory"));
+        }
+        if self.find_theme(&self.theme).is_none() {
+            let names: Vec<&str> = self.themes.iter().map(|t| t.name.as_str()).collect();
+            return Err(format!(
+                "unknown theme '{}', expected one of {}",
+                self.theme,
+                names.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// The index of the theme called `name` in `themes`. Theme files are
+    /// loaded after the built-ins, so a file can replace a built-in theme.
+    pub fn find_theme(&self, name: &str) -> Option<usize> {
+        self.themes.iter().rposition(|t| t.name == name)
+    }
+
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick_rate_ms)
+    }
+
+    pub fn error_timeout(&self) -> Duration {
+        Duration::from_secs(self.error_timeout_secs)
+    }
+
+    /// The configured database, or todos.db in the data directory
+    pub fn database_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = match &self.database {
+            Some(path) => path.clone(),
+            None => project_dirs()
+                .ok_or("Failed to determine project directories")?
+                .data_dir()
+                .join("todos.db"),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(path)
+    }
+
+    /// The config as TOML, with every setting filled in
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("the config is always valid TOML")
+    }
+}
+
+// reads every `*.toml` file in `dir` as a theme named after the file, in
+// name order; a missing directory has no themes
+fn load_themes(dir: &Path) -> Result<Vec<Theme>, ConfigError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(ConfigError::Read {
+                path: dir.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|source| ConfigError::Read {
+                path: dir.to_path_buf(),
+                source,
+            })?
+            .path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let text = fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+                path: path.clone(),
+                source,
+            })?;
+            let colors = ColorScheme::from_toml(&text).map_err(|source| ConfigError::Parse {
+                path: path.clone(),
+                source,
+            })?;
+            let name = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            Ok(Theme { name, colors })
+        })
+        .collect()
+}
+
+/// Where the config is read from when no `--config` is given
+pub fn default_path() -> Result<PathBuf, ConfigError> {
+    let dirs = project_dirs().ok_or(ConfigError::NoConfigDir)?;
+    Ok(dirs.config_dir().join(CONFIG_FILE))
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com", "auxilia", "r_todo")
+}
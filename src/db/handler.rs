@@ -1,16 +1,30 @@
-use crate::models::todo::{Priority, Todo};
-use chrono::{DateTime, Local};
-use rusqlite::{params, Connection, Result as SqlResult};
+use super::migrations;
+use crate::models::{
+    project::Project,
+    todo::{Priority, SearchHit, Todo},
+    view::View,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use rusqlite::{params, Connection, Result as SqlResult, Row, Transaction};
+use std::{collections::HashMap, ops::Range};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     #[error("Failed to connect to database: {0}")]
     ConnectionError(#[from] rusqlite::Error),
+    #[error(
+        "Database schema version {found} is newer than this version of r_todo supports ({supported}); please upgrade r_todo"
+    )]
+    SchemaTooNew { found: i64, supported: i64 },
     // #[error("Failed to exeute database operation: {0}")]
     // OperationError(String),
 }
 
+// the columns `query_todos` expects, in order
+const TODO_COLUMNS: &str = "id, text, completed, created_at, completed_at, priority, due_date, \
+                            due_time, deleted_at, project_id, parent_id, notes, position";
+
 pub struct DatabaseHandler {
     conn: Connection,
 }
@@ -18,54 +32,176 @@ pub struct DatabaseHandler {
 impl DatabaseHandler {
     pub fn new(db_path: &str) -> Result<Self, DatabaseError> {
         let conn = Connection::open(db_path)?;
-        let handler = DatabaseHandler { conn };
-        handler.init_database()?;
-        Ok(handler)
+        Self::from_connection(conn)
     }
 
-    fn init_database(&self) -> Result<(), DatabaseError> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS todos (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                text TEXT NOT NULL,
-                completed BOOLEAN NOT NULL DEFAULT 0,
-                created_at DATETIME NOT NULL,
-                modified_at DATETIME,
-                completed_at DATETIME,
-                deleted_at DATETIME,
-                priority INTEGER
-            )",
-            [],
-        )?;
-
-        Ok(())
+    fn from_connection(mut conn: Connection) -> Result<Self, DatabaseError> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut conn)?;
+        Ok(DatabaseHandler { conn })
     }
 
+    /// Loads the todos that aren't deleted or in an archived project
     pub fn load_todos(&self) -> Result<Vec<Todo>, DatabaseError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, text, completed, created_at, completed_at, priority
+        self.query_todos(&format!(
+            "SELECT {}
              FROM todos
              WHERE deleted_at IS NULL
+               AND project_id IN (SELECT id FROM projects WHERE archived_at IS NULL)
              ORDER BY priority, created_at DESC
             ",
-        )?;
+            TODO_COLUMNS
+        ))
+    }
 
-        let todos = stmt.query_map([], |row| {
-            Ok(Todo {
-                id: row.get(0)?,
-                text: row.get(1)?,
-                completed: row.get(2)?,
-                created_at: row.get(3)?,
-                completed_at: row.get::<_, Option<DateTime<Local>>>(4)?,
-                priority: Self::int_to_priority(row.get(5)?),
+    /// Loads the soft-deleted todos, most recently deleted first
+    pub fn load_deleted_todos(&self) -> Result<Vec<Todo>, DatabaseError> {
+        self.query_todos(&format!(
+            "SELECT {}
+             FROM todos
+             WHERE deleted_at IS NOT NULL
+             ORDER BY deleted_at DESC
+            ",
+            TODO_COLUMNS
+        ))
+    }
+
+    fn query_todos(&self, sql: &str) -> Result<Vec<Todo>, DatabaseError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let todos = stmt.query_map([], Self::todo_from_row)?;
+
+        let mut todos = todos.collect::<SqlResult<Vec<Todo>>>()?;
+        let mut tags = self.load_todo_tags()?;
+        for todo in todos.iter_mut() {
+            todo.tags = tags.remove(&todo.id).unwrap_or_default();
+        }
+
+        Ok(todos)
+    }
+
+    // maps a row selected with `TODO_COLUMNS`, leaving the tags empty
+    fn todo_from_row(row: &Row) -> SqlResult<Todo> {
+        Ok(Todo {
+            id: row.get(0)?,
+            text: row.get(1)?,
+            completed: row.get(2)?,
+            created_at: row.get(3)?,
+            completed_at: row.get::<_, Option<DateTime<Local>>>(4)?,
+            priority: Self::int_to_priority(row.get(5)?),
+            due_date: row.get::<_, Option<NaiveDate>>(6)?,
+            due_time: row.get::<_, Option<NaiveTime>>(7)?,
+            deleted_at: row.get::<_, Option<DateTime<Local>>>(8)?,
+            tags: Vec::new(),
+            project_id: row.get(9)?,
+            parent_id: row.get(10)?,
+            notes: row.get(11)?,
+            position: row.get(12)?,
+        })
+    }
+
+    /// Full-text search over the text and notes of every todo, best matches
+    /// first. Each word of `query` matches words starting with it. Deleted
+    /// todos and todos in archived projects are only searched if
+    /// `include_archived` is set.
+    pub fn search(
+        &self,
+        query: &str,
+        include_archived: bool,
+    ) -> Result<Vec<SearchHit>, DatabaseError> {
+        let query = fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let columns: Vec<String> = TODO_COLUMNS
+            .split(", ")
+            .map(|column| format!("todos.{}", column))
+            .collect();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {},
+                snippet(todos_fts, -1, char(2), char(3), '…', 12),
+                bm25(todos_fts)
+             FROM todos_fts
+             JOIN todos ON todos.id = todos_fts.rowid
+             WHERE todos_fts MATCH ?1
+               AND (?2 OR (todos.deleted_at IS NULL
+                   AND todos.project_id IN (SELECT id FROM projects WHERE archived_at IS NULL)))
+             ORDER BY bm25(todos_fts)",
+            columns.join(", ")
+        ))?;
+
+        // the snippet and rank follow the todo's columns
+        let snippet_column = columns.len();
+        let hits = stmt.query_map(params![query, include_archived], |row| {
+            let (snippet, matches) = parse_snippet(&row.get::<_, String>(snippet_column)?);
+            Ok(SearchHit {
+                todo: Self::todo_from_row(row)?,
+                snippet,
+                matches,
+                rank: row.get(snippet_column + 1)?,
             })
         })?;
 
-        todos
-            .collect::<SqlResult<Vec<Todo>>>()
+        let mut hits = hits.collect::<SqlResult<Vec<SearchHit>>>()?;
+        let mut tags = self.load_todo_tags()?;
+        for hit in hits.iter_mut() {
+            hit.todo.tags = tags.remove(&hit.todo.id).unwrap_or_default();
+        }
+
+        Ok(hits)
+    }
+
+    fn load_todo_tags(&self) -> Result<HashMap<i64, Vec<String>>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT todo_tags.todo_id, tags.name
+             FROM todo_tags
+             JOIN tags ON tags.id = todo_tags.tag_id
+             ORDER BY tags.name",
+        )?;
+
+        let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (todo_id, name) = row?;
+            tags.entry(todo_id).or_default().push(name);
+        }
+
+        Ok(tags)
+    }
+
+    /// Loads the names of all tags that are in use, for autocompletion
+    pub fn load_tag_names(&self) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT tags.name
+             FROM tags
+             JOIN todo_tags ON todo_tags.tag_id = tags.id
+             ORDER BY tags.name",
+        )?;
+
+        let names = stmt.query_map([], |row| row.get(0))?;
+        names
+            .collect::<SqlResult<Vec<String>>>()
             .map_err(DatabaseError::from)
     }
 
+    fn set_tags(tx: &Transaction, todo_id: i64, tags: &[String]) -> SqlResult<()> {
+        tx.execute("DELETE FROM todo_tags WHERE todo_id = ?1", params![todo_id])?;
+
+        for tag in tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
+            tx.execute(
+                "INSERT INTO todo_tags (todo_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                params![todo_id, tag],
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn int_to_priority(priority: i64) -> Option<Priority> {
         match priority {
             3 => Some(Priority::Low),
@@ -88,20 +224,60 @@ impl DatabaseHandler {
         let tx = self.conn.transaction()?;
 
         tx.execute(
-            "INSERT INTO todos (text, completed, created_at, priority) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO todos
+                (text, completed, created_at, priority, due_date, due_time, project_id, parent_id,
+                 notes, position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
                 todo.text,
                 todo.completed,
                 todo.created_at,
-                Self::priority_to_int(todo.priority)
+                Self::priority_to_int(todo.priority),
+                todo.due_date,
+                todo.due_time,
+                todo.project_id,
+                todo.parent_id,
+                todo.notes,
+                todo.position
             ],
         )?;
 
         let id = tx.last_insert_rowid();
+        Self::set_tags(&tx, id, &todo.tags)?;
         tx.commit()?;
         Ok(id)
     }
 
+    /// Puts back a todo that was erased, keeping its id
+    pub fn reinsert_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO todos
+                (id, text, completed, created_at, completed_at, priority, due_date, due_time,
+                 project_id, parent_id, notes, position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                todo.id,
+                todo.text,
+                todo.completed,
+                todo.created_at,
+                todo.completed_at,
+                Self::priority_to_int(todo.priority),
+                todo.due_date,
+                todo.due_time,
+                todo.project_id,
+                todo.parent_id,
+                todo.notes,
+                todo.position
+            ],
+        )?;
+        Self::set_tags(&tx, todo.id, &todo.tags)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
     pub fn update_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
         let tx = self.conn.transaction()?;
 
@@ -111,22 +287,206 @@ impl DatabaseHandler {
                  completed = ?2,
                  modified_at = ?3,
                  completed_at = ?4,
-                 priority = ?5
-             WHERE id = ?6 AND deleted_at IS NULL",
+                 priority = ?5,
+                 due_date = ?6,
+                 due_time = ?7,
+                 project_id = ?8,
+                 parent_id = ?9,
+                 notes = ?10,
+                 position = ?11
+             WHERE id = ?12 AND deleted_at IS NULL",
             params![
                 todo.text,
                 todo.completed,
                 Local::now(),
                 todo.completed_at,
                 Self::priority_to_int(todo.priority),
+                todo.due_date,
+                todo.due_time,
+                todo.project_id,
+                todo.parent_id,
+                todo.notes,
+                todo.position,
                 todo.id
             ],
         )?;
+        Self::set_tags(&tx, todo.id, &todo.tags)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The position that puts a new todo after every other todo
+    pub fn next_position(&self) -> Result<i64, DatabaseError> {
+        let position = self.conn.query_row(
+            "SELECT COALESCE(MAX(position), 0) + ?1 FROM todos",
+            params![migrations::POSITION_GAP],
+            |row| row.get(0),
+        )?;
+        Ok(position)
+    }
+
+    /// Spreads the positions of every todo, deleted ones included, evenly
+    /// again without changing their order, and returns the new positions
+    pub fn renumber_positions(&mut self) -> Result<HashMap<i64, i64>, DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        let ids = {
+            let mut stmt = tx.prepare("SELECT id FROM todos ORDER BY position, id")?;
+            let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+            ids.collect::<SqlResult<Vec<i64>>>()?
+        };
+
+        let mut positions = HashMap::with_capacity(ids.len());
+        {
+            let mut stmt = tx.prepare("UPDATE todos SET position = ?1 WHERE id = ?2")?;
+            for (i, id) in ids.into_iter().enumerate() {
+                let position = (i as i64 + 1) * migrations::POSITION_GAP;
+                stmt.execute(params![position, id])?;
+                positions.insert(id, position);
+            }
+        }
+
+        tx.commit()?;
+        Ok(positions)
+    }
+
+    /// Loads the projects that haven't been archived, oldest first
+    pub fn load_projects(&self) -> Result<Vec<Project>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, created_at, archived_at
+             FROM projects
+             WHERE archived_at IS NULL
+             ORDER BY id",
+        )?;
+
+        let projects = stmt.query_map([], |row| {
+            Ok(Project {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                archived_at: row.get(3)?,
+            })
+        })?;
+
+        projects
+            .collect::<SqlResult<Vec<Project>>>()
+            .map_err(DatabaseError::from)
+    }
+
+    pub fn insert_project(&mut self, project: &Project) -> Result<i64, DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO projects (name, created_at) VALUES (?1, ?2)",
+            params![project.name, project.created_at],
+        )?;
+
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn rename_project(&mut self, id: i64, name: &str) -> Result<(), DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "UPDATE projects SET name = ?1 WHERE id = ?2",
+            params![name, id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Hides a project from the tab bar. Its todos are kept in the database.
+    pub fn archive_project(&mut self, id: i64) -> Result<(), DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "UPDATE projects SET archived_at = ?1 WHERE id = ?2",
+            params![Local::now(), id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Loads the saved views, in the order they were created
+    pub fn load_views(&self) -> Result<Vec<View>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, filter, sort
+             FROM views
+             ORDER BY id",
+        )?;
+
+        let views = stmt.query_map([], |row| {
+            Ok(View {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                filter: row.get(2)?,
+                // fall back to the default sort rather than failing to load
+                sort: row.get::<_, String>(3)?.parse().unwrap_or_default(),
+            })
+        })?;
+
+        views
+            .collect::<SqlResult<Vec<View>>>()
+            .map_err(DatabaseError::from)
+    }
+
+    /// Saves a view, replacing any saved view with the same name, and
+    /// returns its id
+    pub fn save_view(&mut self, view: &View) -> Result<i64, DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        let id = tx.query_row(
+            "INSERT INTO views (name, filter, sort, created_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (name) DO UPDATE
+             SET filter = excluded.filter,
+                 sort = excluded.sort
+             RETURNING id",
+            params![view.name, view.filter, view.sort.to_string(), Local::now()],
+            |row| row.get(0),
+        )?;
+
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn delete_view(&mut self, id: i64) -> Result<(), DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute("DELETE FROM views WHERE id = ?1", params![id])?;
 
         tx.commit()?;
         Ok(())
     }
 
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM settings WHERE key = ?1")?;
+        let mut rows = stmt.query_map(params![key], |row| row.get(0))?;
+        rows.next().transpose().map_err(DatabaseError::from)
+    }
+
+    pub fn delete_setting(&mut self, key: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    pub fn set_setting(&mut self, key: &str, value: &str) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
     pub fn delete_todo(&mut self, id: i64) -> Result<(), DatabaseError> {
         let tx = self.conn.transaction()?;
 
@@ -138,4 +498,273 @@ impl DatabaseHandler {
         tx.commit()?;
         Ok(())
     }
+
+    /// Moves a soft-deleted todo back out of the trash
+    pub fn restore_todo(&mut self, id: i64) -> Result<(), DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "UPDATE todos SET deleted_at = NULL, modified_at = ?1 WHERE id = ?2",
+            params![Local::now(), id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Permanently removes the soft-deleted todos among `ids`, returning how
+    /// many were removed
+    pub fn purge_todos(&mut self, ids: &[i64]) -> Result<usize, DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        let mut purged = 0;
+        for id in ids {
+            purged += tx.execute(
+                "DELETE FROM todos WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )?;
+        }
+        Self::detach_orphans(&tx)?;
+
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    // `parent_id` has no foreign key, so subtasks of a purged todo are made
+    // top-level todos by hand rather than left pointing at nothing
+    fn detach_orphans(tx: &Transaction) -> SqlResult<()> {
+        tx.execute(
+            "UPDATE todos SET parent_id = NULL
+             WHERE parent_id IS NOT NULL AND parent_id NOT IN (SELECT id FROM todos)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Removes a todo for good without it passing through the trash
+    pub fn erase_todo(&mut self, id: i64) -> Result<(), DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute("DELETE FROM todos WHERE id = ?1", params![id])?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Permanently removes every todo that was deleted before `cutoff`,
+    /// returning the ids of the purged todos
+    pub fn purge_deleted_before(
+        &mut self,
+        cutoff: DateTime<Local>,
+    ) -> Result<Vec<i64>, DatabaseError> {
+        let tx = self.conn.transaction()?;
+
+        let purged = tx
+            .prepare(
+                "DELETE FROM todos
+                 WHERE deleted_at IS NOT NULL AND datetime(deleted_at) < datetime(?1)
+                 RETURNING id",
+            )?
+            .query_map(params![cutoff], |row| row.get(0))?
+            .collect::<SqlResult<Vec<i64>>>()?;
+        Self::detach_orphans(&tx)?;
+
+        tx.commit()?;
+        Ok(purged)
+    }
+}
+
+// Turns user input into an FTS5 query, quoting each word so punctuation
+// can't be mistaken for query syntax and matching it as a prefix
+fn fts_query(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Strips the match markers `snippet()` was asked to insert, returning the
+// plain text and the byte ranges they surrounded
+fn parse_snippet(raw: &str) -> (String, Vec<Range<usize>>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut matches = Vec::new();
+    let mut start = 0;
+    for c in raw.chars() {
+        match c {
+            '\u{2}' => start = text.len(),
+            '\u{3}' => matches.push(start..text.len()),
+            c => text.push(c),
+        }
+    }
+    (text, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::{schema_version, LATEST_VERSION};
+
+    // the schema as it shipped before versioned migrations existed
+    fn v0_fixture() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE todos (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                text TEXT NOT NULL,
+                completed BOOLEAN NOT NULL DEFAULT 0,
+                created_at DATETIME NOT NULL,
+                modified_at DATETIME,
+                completed_at DATETIME,
+                deleted_at DATETIME,
+                priority INTEGER
+            );
+            INSERT INTO todos (text, completed, created_at, priority)
+            VALUES ('write migrations', 0, '2025-01-01T09:00:00+00:00', 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn fresh_database_is_at_latest_version() {
+        let handler =
+            DatabaseHandler::from_connection(Connection::open_in_memory().unwrap()).unwrap();
+
+        assert_eq!(schema_version(&handler.conn).unwrap(), LATEST_VERSION);
+        assert!(handler.load_todos().unwrap().is_empty());
+    }
+
+    #[test]
+    fn v0_database_is_upgraded_and_keeps_its_rows() {
+        let conn = v0_fixture();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        let handler = DatabaseHandler::from_connection(conn).unwrap();
+        assert_eq!(schema_version(&handler.conn).unwrap(), LATEST_VERSION);
+
+        let todos = handler.load_todos().unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "write migrations");
+        assert_eq!(todos[0].priority, Some(Priority::High));
+        assert_eq!(todos[0].due_date, None);
+    }
+
+    #[test]
+    fn unversioned_database_with_newer_columns_is_upgraded() {
+        let conn = v0_fixture();
+        conn.execute_batch(
+            "ALTER TABLE todos ADD COLUMN due_date DATE;
+             ALTER TABLE todos ADD COLUMN due_time TIME;",
+        )
+        .unwrap();
+
+        let handler = DatabaseHandler::from_connection(conn).unwrap();
+        assert_eq!(schema_version(&handler.conn).unwrap(), LATEST_VERSION);
+        assert_eq!(handler.load_todos().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn upgraded_database_accepts_new_columns() {
+        let mut handler = DatabaseHandler::from_connection(v0_fixture()).unwrap();
+
+        let mut todo = Todo::new(String::from("ship it"));
+        todo.priority = Some(Priority::Low);
+        todo.due_date = NaiveDate::from_ymd_opt(2026, 3, 1);
+        let id = handler.insert_todo(&todo).unwrap();
+
+        let loaded = handler.load_todos().unwrap();
+        let loaded = loaded.iter().find(|t| t.id == id).unwrap();
+        assert_eq!(loaded.due_date, todo.due_date);
+    }
+
+    #[test]
+    fn newer_database_is_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", LATEST_VERSION + 1)
+            .unwrap();
+
+        match DatabaseHandler::from_connection(conn) {
+            Err(DatabaseError::SchemaTooNew { found, supported }) => {
+                assert_eq!(found, LATEST_VERSION + 1);
+                assert_eq!(supported, LATEST_VERSION);
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("a newer schema should be refused"),
+        }
+    }
+
+    #[test]
+    fn search_index_follows_upgrades_and_edits() {
+        let mut handler = DatabaseHandler::from_connection(v0_fixture()).unwrap();
+        let ids = |handler: &DatabaseHandler, query| -> Vec<i64> {
+            let hits = handler.search(query, true).unwrap();
+            hits.iter().map(|hit| hit.todo.id).collect()
+        };
+
+        // rows from before the index existed are indexed by the migration
+        assert_eq!(ids(&handler, "migr").len(), 1);
+
+        let mut todo = Todo::new(String::from("fix login bug"));
+        todo.id = handler.insert_todo(&todo).unwrap();
+        assert_eq!(ids(&handler, "login"), vec![todo.id]);
+
+        todo.text = String::from("fix logout bug");
+        todo.notes = String::from("only with SSO");
+        handler.update_todo(&todo).unwrap();
+        assert!(ids(&handler, "login").is_empty());
+        assert_eq!(ids(&handler, "sso"), vec![todo.id]);
+
+        handler.delete_todo(todo.id).unwrap();
+        assert!(handler.search("sso", false).unwrap().is_empty());
+        assert_eq!(ids(&handler, "sso"), vec![todo.id]);
+
+        handler.purge_todos(&[todo.id]).unwrap();
+        assert!(ids(&handler, "sso").is_empty());
+    }
+
+    #[test]
+    fn saved_views_keep_every_sort_key() {
+        let mut handler = DatabaseHandler::from_connection(v0_fixture()).unwrap();
+
+        let mut view = View {
+            id: None,
+            name: String::from("Triage"),
+            filter: String::from("open"),
+            sort: "status, -priority, created".parse().unwrap(),
+        };
+        let id = handler.save_view(&view).unwrap();
+
+        // saving under the same name replaces the view
+        view.sort = "-due, priority".parse().unwrap();
+        assert_eq!(handler.save_view(&view).unwrap(), id);
+
+        let views = handler.load_views().unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].sort, view.sort);
+        assert_eq!(views[0].sort.to_string(), "-due, priority");
+    }
+
+    #[test]
+    fn positions_are_numbered_with_gaps_and_renumbered_in_order() {
+        let mut handler = DatabaseHandler::from_connection(v0_fixture()).unwrap();
+        let gap = migrations::POSITION_GAP;
+
+        // the migration puts existing rows in creation order
+        assert_eq!(handler.load_todos().unwrap()[0].position, gap);
+        assert_eq!(handler.next_position().unwrap(), 2 * gap);
+
+        // squeeze two todos in front of the existing one
+        let mut ids = Vec::new();
+        for position in [gap - 1, gap - 2] {
+            let mut todo = Todo::new(format!("at {}", position));
+            todo.position = position;
+            ids.push(handler.insert_todo(&todo).unwrap());
+        }
+
+        let positions = handler.renumber_positions().unwrap();
+        assert_eq!(positions[&ids[1]], gap);
+        assert_eq!(positions[&ids[0]], 2 * gap);
+        assert_eq!(positions.values().max(), Some(&(3 * gap)));
+    }
 }
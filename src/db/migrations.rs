@@ -0,0 +1,223 @@
+use chrono::Local;
+use rusqlite::{params, Connection, Result as SqlResult, Transaction};
+
+use super::handler::DatabaseError;
+use crate::models::project::DEFAULT_PROJECT_ID;
+
+/// A single schema upgrade, taking the database from version `n` to `n + 1`
+type Migration = fn(&Transaction) -> SqlResult<()>;
+
+/// Ordered list of schema migrations. The schema version stored in
+/// `PRAGMA user_version` is the number of migrations that have been applied,
+/// so new steps must only ever be appended to the end of this list.
+const MIGRATIONS: &[Migration] = &[
+    create_todos_table,
+    add_due_date_columns,
+    create_tag_tables,
+    create_projects_table,
+    add_parent_id_column,
+    add_notes_column,
+    create_search_index,
+    create_views_tables,
+    merge_view_sort_direction,
+    add_position_column,
+];
+
+/// The space left between the positions of neighbouring todos when they are
+/// numbered, so a todo can usually be moved by changing only its own position
+pub const POSITION_GAP: i64 = 1024;
+
+/// The schema version this binary expects
+pub const LATEST_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Brings the database up to `LATEST_VERSION`, applying all pending
+/// migrations in a single transaction.
+pub fn migrate(conn: &mut Connection) -> Result<(), DatabaseError> {
+    let current = schema_version(conn)?;
+
+    if current > LATEST_VERSION {
+        return Err(DatabaseError::SchemaTooNew {
+            found: current,
+            supported: LATEST_VERSION,
+        });
+    }
+
+    if current == LATEST_VERSION {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for migration in &MIGRATIONS[current as usize..] {
+        migration(&tx)?;
+    }
+    tx.pragma_update(None, "user_version", LATEST_VERSION)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn schema_version(conn: &Connection) -> SqlResult<i64> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+fn column_exists(tx: &Transaction, table: &str, column: &str) -> SqlResult<bool> {
+    let mut stmt = tx.prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?;
+    stmt.exists([table, column])
+}
+
+// Unversioned databases may already contain columns added by later
+// migrations, so column additions have to tolerate that.
+fn add_column_if_missing(
+    tx: &Transaction,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> SqlResult<()> {
+    if !column_exists(tx, table, column)? {
+        tx.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+// v1: the original todos table
+fn create_todos_table(tx: &Transaction) -> SqlResult<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS todos (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            text TEXT NOT NULL,
+            completed BOOLEAN NOT NULL DEFAULT 0,
+            created_at DATETIME NOT NULL,
+            modified_at DATETIME,
+            completed_at DATETIME,
+            deleted_at DATETIME,
+            priority INTEGER
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// v2: optional due date and time
+fn add_due_date_columns(tx: &Transaction) -> SqlResult<()> {
+    add_column_if_missing(tx, "todos", "due_date", "DATE")?;
+    add_column_if_missing(tx, "todos", "due_time", "TIME")
+}
+
+// v3: tags, in a many-to-many relationship with todos
+fn create_tag_tables(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS todo_tags (
+            todo_id INTEGER NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (todo_id, tag_id)
+        );
+        CREATE INDEX IF NOT EXISTS todo_tags_tag_id ON todo_tags(tag_id);",
+    )
+}
+
+// v4: named projects, with every existing todo moved into the default project
+fn create_projects_table(tx: &Transaction) -> SqlResult<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at DATETIME NOT NULL,
+            archived_at DATETIME
+        )",
+        [],
+    )?;
+    tx.execute(
+        "INSERT OR IGNORE INTO projects (id, name, created_at) VALUES (?1, 'Inbox', ?2)",
+        params![DEFAULT_PROJECT_ID, Local::now()],
+    )?;
+    add_column_if_missing(
+        tx,
+        "todos",
+        "project_id",
+        &format!("INTEGER NOT NULL DEFAULT {}", DEFAULT_PROJECT_ID),
+    )
+}
+
+// v5: subtasks, pointing at their parent todo
+fn add_parent_id_column(tx: &Transaction) -> SqlResult<()> {
+    add_column_if_missing(tx, "todos", "parent_id", "INTEGER")?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS todos_parent_id ON todos(parent_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+// v6: free-form notes
+fn add_notes_column(tx: &Transaction) -> SqlResult<()> {
+    add_column_if_missing(tx, "todos", "notes", "TEXT NOT NULL DEFAULT ''")
+}
+
+// v7: full-text index over text and notes, kept up to date by triggers
+fn create_search_index(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS todos_fts
+            USING fts5(text, notes, content = 'todos', content_rowid = 'id');
+        CREATE TRIGGER IF NOT EXISTS todos_fts_insert AFTER INSERT ON todos BEGIN
+            INSERT INTO todos_fts (rowid, text, notes) VALUES (new.id, new.text, new.notes);
+        END;
+        CREATE TRIGGER IF NOT EXISTS todos_fts_delete AFTER DELETE ON todos BEGIN
+            INSERT INTO todos_fts (todos_fts, rowid, text, notes)
+                VALUES ('delete', old.id, old.text, old.notes);
+        END;
+        CREATE TRIGGER IF NOT EXISTS todos_fts_update AFTER UPDATE OF text, notes ON todos BEGIN
+            INSERT INTO todos_fts (todos_fts, rowid, text, notes)
+                VALUES ('delete', old.id, old.text, old.notes);
+            INSERT INTO todos_fts (rowid, text, notes) VALUES (new.id, new.text, new.notes);
+        END;
+        INSERT INTO todos_fts (todos_fts) VALUES ('rebuild');",
+    )
+}
+
+// v8: saved views, and a key-value store for settings such as the last view
+fn create_views_tables(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS views (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            filter TEXT NOT NULL DEFAULT '',
+            sort_by TEXT NOT NULL,
+            sort_asc BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+// v9: views store a whole sort specification such as `status, -priority`
+// instead of a single key and direction
+fn merge_view_sort_direction(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "UPDATE views SET sort_by = '-' || sort_by WHERE NOT sort_asc;
+        ALTER TABLE views DROP COLUMN sort_asc;
+        ALTER TABLE views RENAME COLUMN sort_by TO sort;",
+    )
+}
+
+// v10: manual ordering, with existing todos put in creation order and room
+// left to move todos between any two of them
+fn add_position_column(tx: &Transaction) -> SqlResult<()> {
+    add_column_if_missing(tx, "todos", "position", "INTEGER NOT NULL DEFAULT 0")?;
+    tx.execute_batch(&format!(
+        "UPDATE todos
+        SET position = numbered.row * {gap}
+        FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS row FROM todos) AS numbered
+        WHERE numbered.id = todos.id;",
+        gap = POSITION_GAP
+    ))
+}
@@ -0,0 +1,45 @@
+//! The todo model and storage behind r_todo, for tools that want to read
+//! and change the same todos as the app.
+//!
+//! [`TodoStore`] is the way in: it opens a database (creating it or bringing
+//! its schema up to date as needed), lists todos through a [`Query`], and
+//! adds, updates, completes and deletes them, each change undoable. The
+//! [`models`] are plain data; [`db`] is the SQLite layer underneath the store.
+//!
+//! ```
+//! use r_todo::{Priority, Query, TodoFields, TodoStore, DEFAULT_PROJECT_ID};
+//!
+//! let mut store = TodoStore::open(":memory:")?;
+//! let id = store.add(
+//!     TodoFields {
+//!         priority: Some(Priority::High),
+//!         tags: vec![String::from("home")],
+//!         ..TodoFields::new("Water the plants")
+//!     },
+//!     DEFAULT_PROJECT_ID,
+//! )?;
+//!
+//! let home = Query {
+//!     tags: vec![String::from("home")],
+//!     ..Query::default()
+//! };
+//! assert_eq!(store.query(&home)[0].id, id);
+//!
+//! store.delete(id)?;
+//! assert!(store.todos().is_empty());
+//! # Ok::<(), r_todo::StoreError>(())
+//! ```
+
+pub mod db;
+pub mod models;
+pub mod store;
+
+pub use models::{
+    filter::Filter,
+    project::{Project, DEFAULT_PROJECT_ID},
+    todo::{Priority, Todo},
+};
+pub use store::{
+    query::Query,
+    todo_store::{StoreError, TodoFields, TodoStore},
+};
@@ -1,28 +1,60 @@
 mod app;
-mod db;
-mod models;
+mod cli;
+mod config;
 mod ui;
 
-use crate::models::todo::Priority;
-use app::state::{App, Mode, SortCriteria};
+// the model, storage and UI-free core come from the library
+use r_todo::{db, models, store};
+
+use app::{action::Effect, front_matter, input::Input, state::App};
+use clap::Parser;
+use cli::{Cli, Command};
+use config::Config;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use directories::ProjectDirs;
 use ratatui::{backend::CrosstermBackend, Terminal};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
 use std::{
-    fs, io,
-    path::PathBuf,
+    collections::hash_map::RandomState,
+    env,
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
     time::{Duration, Instant},
 };
-use ui::edit_popup::{EditingState, InputFields, SelectableField};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let (mut config, config_path) = match Config::load(cli.config.as_deref()) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     // initialize app with database
-    let db_path = get_database_path()?;
-    let mut app = match App::new(db_path.to_str().unwrap()) {
+    let db_path = config.database_path()?;
+    if let Some(Command::Config) = cli.command {
+        match config_path {
+            Some(path) => println!("# read from {}", path.display()),
+            None => println!("# defaults, no config file found"),
+        }
+        config.database = Some(db_path);
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+
+    let mut app = match App::new(db_path.to_str().unwrap(), config) {
         Ok(app) => app,
         Err(e) => {
             eprintln!("Failed to initialize database: {}", e);
@@ -30,16 +62,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // subcommands run without ever touching the terminal setup
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command, &mut app) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Err(e) = app.restore_theme() {
+        app.set_error(format!("Failed to restore the theme: {}", e));
+    }
+    if let Err(e) = app.restore_sort() {
+        app.set_error(format!("Failed to restore the sort: {}", e));
+    }
+    if let Err(e) = app.restore_last_view() {
+        app.set_error(format!("Failed to restore the last view: {}", e));
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
-    let mut last_selected = 0;
+    let mut input = Input::default();
 
     let mut last_tick = Instant::now();
-    let tick_rate = Duration::from_millis(20);
+    let tick_rate = app.config.tick_rate();
 
     // main loop
     loop {
@@ -53,177 +109,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // poll for user events
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match app.mode {
-                    Mode::Normal => match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('h') => app.show_help = !app.show_help,
-                        KeyCode::Char('n') => {
-                            app.editing_state = EditingState {
-                                input_fields: InputFields {
-                                    text: String::new(),
-                                    priority: Some(Priority::Medium),
-                                },
-                                selected_field: Some(SelectableField::Text),
-                            };
-                            last_selected = app.table_state.selected().unwrap_or(0);
-                            app.table_state.select(None);
-                            app.mode = Mode::Editing;
-                        }
-                        KeyCode::Char('e') => {
-                            if let Some(selected) = app.table_state.selected() {
-                                if let Some(todo) = app.todos.get(selected) {
-                                    app.editing_state = EditingState {
-                                        input_fields: InputFields {
-                                            text: todo.text.clone(),
-                                            priority: todo.priority,
-                                        },
-                                        selected_field: Some(SelectableField::Text),
-                                    };
-                                    app.mode = Mode::Editing;
-                                }
-                            }
-                        }
-                        KeyCode::Char('d') => {
-                            if let Some(selected) = app.table_state.selected() {
-                                if let Err(e) = app.delete_todo(selected) {
-                                    app.set_error(format!("Failed to delete todo: {}", e));
-                                }
-                            }
-                        }
-                        KeyCode::Char('c') => {
-                            if let Err(e) = app.clear_completed() {
-                                app.set_error(format!("Failed to clear completed todos: {}", e));
-                            }
-                        }
-                        KeyCode::Char(' ') => {
-                            if let Some(selected) = app.table_state.selected() {
-                                if let Err(e) = app.toggle_todo(selected) {
-                                    app.set_error(format!("Failed to toggle todo: {}", e));
-                                }
-                            }
-                        }
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            app.select_previous();
-                        }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            app.select_next();
-                        }
-                        KeyCode::Char('p') => {
-                            app.set_sort_criteria(SortCriteria::Priority);
-                        }
-                        KeyCode::Char('t') => {
-                            app.set_sort_criteria(SortCriteria::CreatedDate);
-                        }
-                        KeyCode::Char('s') => {
-                            app.set_sort_criteria(SortCriteria::Completed);
-                        }
-                        KeyCode::Char('r') => {
-                            app.toggle_sort_direction();
-                            app.sort_todos();
-                        }
-                        _ => {}
-                    },
-                    Mode::Editing => match key.code {
-                        KeyCode::Char(c) => {
-                            if let Some(SelectableField::Text) = app.editing_state.selected_field {
-                                app.editing_state.input_fields.text.push(c);
-                            } else if let Some(SelectableField::Priority) =
-                                app.editing_state.selected_field
-                            {
-                                // allow the user to set the priority directly
-                                if c == 'h' || c == '1' {
-                                    app.editing_state.input_fields.priority = Some(Priority::High);
-                                } else if c == 'm' || c == '2' {
-                                    app.editing_state.input_fields.priority =
-                                        Some(Priority::Medium);
-                                } else if c == 'l' || c == '3' {
-                                    app.editing_state.input_fields.priority = Some(Priority::Low);
-                                }
-
-                                // allow the user to naviagate through the priority options
-                                if c == 'j' {
-                                    let current = app
-                                        .editing_state
-                                        .input_fields
-                                        .priority
-                                        .unwrap_or(Priority::Medium);
-                                    let next = match current {
-                                        Priority::High => Priority::Medium,
-                                        Priority::Medium => Priority::Low,
-                                        Priority::Low => Priority::High,
-                                    };
-                                    app.editing_state.input_fields.priority = Some(next);
-                                } else if c == 'k' {
-                                    let current = app
-                                        .editing_state
-                                        .input_fields
-                                        .priority
-                                        .unwrap_or(Priority::Medium);
-                                    let next = match current {
-                                        Priority::High => Priority::Low,
-                                        Priority::Medium => Priority::High,
-                                        Priority::Low => Priority::Medium,
-                                    };
-                                    app.editing_state.input_fields.priority = Some(next);
-                                }
-                            } else {
-                                continue;
-                            }
-                        }
-                        KeyCode::Backspace => {
-                            if let Some(SelectableField::Text) = app.editing_state.selected_field {
-                                app.editing_state.input_fields.text.pop();
-                            }
-                        }
-                        KeyCode::Tab => {
-                            app.editing_state.selected_field =
-                                match app.editing_state.selected_field {
-                                    Some(SelectableField::Text) => Some(SelectableField::Priority),
-                                    Some(SelectableField::Priority) => Some(SelectableField::Text),
-                                    None => Some(SelectableField::Text),
-                                };
-                        }
-                        KeyCode::Enter => {
-                            if !app.editing_state.input_fields.text.is_empty() {
-                                let priority = app
-                                    .editing_state
-                                    .input_fields
-                                    .priority
-                                    .unwrap_or(Priority::Medium);
-
-                                if let Some(selected) = app.table_state.selected() {
-                                    if let Err(e) = app.update_todo(
-                                        selected,
-                                        app.editing_state.input_fields.text.clone(),
-                                        priority,
-                                    ) {
-                                        app.set_error(format!("Failed to update todo: {}", e));
-                                    }
-                                } else {
-                                    if let Err(e) = app.add_todo(
-                                        app.editing_state.input_fields.text.clone(),
-                                        priority,
-                                    ) {
-                                        app.set_error(format!("Failed to add todo: {}", e));
-                                    }
-
-                                    app.table_state.select(Some(last_selected));
-                                }
-
-                                app.editing_state.input_fields.text.clear();
-                                app.editing_state.input_fields.priority = None;
-                                app.mode = Mode::Normal;
-                                terminal.clear()?;
-                            }
-                        }
-                        KeyCode::Esc => {
-                            app.editing_state.input_fields.text.clear();
-                            app.editing_state.input_fields.priority = None;
-                            app.mode = Mode::Normal;
-                        }
-                        _ => {}
-                    },
+            if let Some(action) = input.action(&app, event::read()?) {
+                match app.handle(action) {
+                    Ok(Effect::Quit) => break,
+                    Ok(Effect::Redraw) => terminal.clear()?,
+                    Ok(Effect::EditExternally(index)) => {
+                        edit_externally(&mut terminal, &mut app, index)?
+                    }
+                    Ok(Effect::None | Effect::Added(_)) => {}
+                    Err(e) => app.set_error(e.to_string()),
                 }
             }
         }
@@ -242,19 +136,112 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         terminal.backend_mut(),
         LeaveAlternateScreen,
         DisableMouseCapture,
+        DisableBracketedPaste,
     )?;
     terminal.show_cursor()?;
 
     Ok(())
 }
 
-// this project dir will be appropriate for respective OSs
-fn get_database_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let proj_dirs = ProjectDirs::from("com", "auxilia", "r_todo")
-        .ok_or("Failed to determine project directories")?;
+/// Suspends the TUI and edits the todo at `index` in `$VISUAL` or `$EDITOR`,
+/// applying the result once the editor exits
+fn edit_externally(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    index: usize,
+) -> io::Result<()> {
+    let todo = &app.store.todos()[index];
+    let original = front_matter::format_todo(todo);
+    let (path, mut file) = create_temp_file(todo.id)?;
+    file.write_all(original.as_bytes())?;
+    drop(file);
 
-    let data_dir = proj_dirs.data_dir();
-    fs::create_dir_all(data_dir)?;
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+
+    let status = run_editor(&path);
+
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!("Editor exited with {}", status))),
+        Err(e) => Err(e),
+    };
+    let edited = match edited {
+        Ok(edited) => edited,
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            app.set_error(format!("Failed to edit todo: {}", e));
+            return Ok(());
+        }
+    };
+
+    if edited == original {
+        let _ = fs::remove_file(&path);
+        return Ok(());
+    }
+
+    match front_matter::parse_todo(&edited) {
+        Ok(fields) => {
+            let _ = fs::remove_file(&path);
+            if let Err(e) = app.update_todo(index, fields) {
+                app.set_error(format!("Failed to update todo: {}", e));
+            }
+        }
+        // keep the file around so the edits aren't lost
+        Err(e) => app.set_error(format!("{}\nYour edits were kept in {}", e, path.display())),
+    }
+
+    Ok(())
+}
+
+/// Creates a file in the temp directory to edit the todo with id `id` in. The
+/// name is random and the file must not exist yet, so nothing planted at a
+/// predictable path, like a symlink, is ever written through.
+fn create_temp_file(id: i64) -> io::Result<(PathBuf, File)> {
+    loop {
+        let random = RandomState::new().build_hasher().finish();
+        let path = env::temp_dir().join(format!("r_todo-{}-{:016x}.md", id, random));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
 
-    Ok(data_dir.join("todos.db"))
+/// Runs `$VISUAL`, then `$EDITOR`, then `vi` on `path`, waiting for it to exit.
+/// The variables may include arguments, as in `code --wait`.
+fn run_editor(path: &Path) -> io::Result<process::ExitStatus> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"));
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
 }
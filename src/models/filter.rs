@@ -0,0 +1,691 @@
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+
+use super::{
+    project::Project,
+    todo::{parse_tags, Priority, Todo},
+};
+
+/// A parsed filter query such as
+/// `priority:high -done tag:infra created:>2026-01-01 "login bug"`.
+///
+/// Terms are combined with AND, `OR` between terms combines them with OR,
+/// a leading `-` negates a term and parentheses group terms.
+///
+/// | Term                   | Matches todos                                  |
+/// |------------------------|------------------------------------------------|
+/// | `word`, `"a phrase"`   | whose text or notes contain it, ignoring case  |
+/// | `done`, `open`         | that are completed, or not                     |
+/// | `overdue`              | that are past their due date                   |
+/// | `priority:high`        | with that priority                             |
+/// | `tag:infra`, `#infra`  | carrying that tag                              |
+/// | `project:"Release 2"`  | in that project                                |
+/// | `created:>2026-01-01`  | created after that date; also `>=`, `<`, `<=`  |
+/// | `due:<=today`          | due on or before today; `due:none` has no date |
+/// | `completed:>=-7d`      | completed in the last week; `+3d` is in 3 days |
+/// | `completed:yesterday`  | completed on that date                         |
+#[derive(Debug, Clone)]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+/// The filter AST
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Text(String),
+    Done,
+    Open,
+    Overdue,
+    Priority(Priority),
+    Tag(String),
+    Project(String),
+    Date(DateField, Comparison, DateValue),
+    NoDueDate,
+}
+
+/// A date to compare against, either fixed or relative to the day the filter
+/// is matched on, so a saved filter like `due:<=today` keeps meaning today
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateValue {
+    Fixed(NaiveDate),
+    /// days from today, so `today` is 0 and `yesterday` is -1
+    Relative(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Created,
+    Due,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Before,
+    OnOrBefore,
+    On,
+    OnOrAfter,
+    After,
+}
+
+/// What a filter needs to know beyond the todo itself
+pub struct FilterContext<'a> {
+    pub now: DateTime<Local>,
+    pub projects: &'a [Project],
+}
+
+/// A filter that couldn't be parsed, pointing at the offending part of the query
+#[derive(Error, Debug)]
+#[error("Invalid filter: {message}\n  {query}\n  {marker}^", marker = " ".repeat(*.column))]
+pub struct FilterError {
+    pub message: String,
+    /// char offset of the problem within `query`
+    pub column: usize,
+    pub query: String,
+}
+
+const FIELDS: &str = "priority, tag, project, created, due, completed";
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Filter, FilterError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            source: input,
+        };
+
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            // parse_or only stops early on a closing parenthesis
+            return Err(parser.error(token.column, "Unmatched ')'"));
+        }
+
+        Ok(Filter {
+            source: input.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// The query the filter was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn matches(&self, todo: &Todo, context: &FilterContext) -> bool {
+        self.expr.matches(todo, context)
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, todo: &Todo, context: &FilterContext) -> bool {
+        match self {
+            Expr::And(exprs) => exprs.iter().all(|e| e.matches(todo, context)),
+            Expr::Or(exprs) => exprs.iter().any(|e| e.matches(todo, context)),
+            Expr::Not(expr) => !expr.matches(todo, context),
+            Expr::Text(text) => {
+                let text = text.to_lowercase();
+                todo.text.to_lowercase().contains(&text)
+                    || todo.notes.to_lowercase().contains(&text)
+            }
+            Expr::Done => todo.completed,
+            Expr::Open => !todo.completed,
+            Expr::Overdue => todo.is_overdue(context.now),
+            Expr::Priority(priority) => todo.priority == Some(*priority),
+            Expr::Tag(tag) => todo.tags.contains(tag),
+            Expr::Project(name) => context
+                .projects
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(name))
+                .is_some_and(|p| p.id == todo.project_id),
+            Expr::Date(field, comparison, date) => {
+                let Some(date) = date.resolve(context.now.date_naive()) else {
+                    return false;
+                };
+                let value = match field {
+                    DateField::Created => Some(todo.created_at.date_naive()),
+                    DateField::Due => todo.due_date,
+                    DateField::Completed => todo.completed_at.map(|d| d.date_naive()),
+                };
+                value.is_some_and(|value| comparison.holds(value, date))
+            }
+            Expr::NoDueDate => todo.due_date.is_none(),
+        }
+    }
+}
+
+impl DateValue {
+    /// The date this stands for when `today` is today, or None if that is
+    /// out of the range of dates
+    pub fn resolve(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            DateValue::Fixed(date) => Some(date),
+            DateValue::Relative(days) => today.checked_add_signed(TimeDelta::try_days(days)?),
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, value: NaiveDate, date: NaiveDate) -> bool {
+        match self {
+            Comparison::Before => value < date,
+            Comparison::OnOrBefore => value <= date,
+            Comparison::On => value == date,
+            Comparison::OnOrAfter => value >= date,
+            Comparison::After => value > date,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    /// a bare word, possibly `field:value`
+    Word(String),
+    /// a double quoted phrase
+    Phrase(String),
+    Not,
+    Or,
+    Open,
+    Close,
+}
+
+struct Token {
+    kind: TokenKind,
+    /// char offset of the token within the query
+    column: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let error = |column, message: &str| FilterError {
+        message: message.to_string(),
+        column,
+        query: input.to_string(),
+    };
+
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let column = i;
+        let kind = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::Open
+            }
+            ')' => {
+                i += 1;
+                TokenKind::Close
+            }
+            '-' if chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) => {
+                i += 1;
+                TokenKind::Not
+            }
+            '"' => {
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j] == '"')
+                    .ok_or_else(|| error(i, "Unclosed quote"))?;
+                let phrase = chars[i + 1..end].iter().collect();
+                i = end + 1;
+                TokenKind::Phrase(phrase)
+            }
+            _ => {
+                // a word runs to the next space or parenthesis, and may
+                // contain a quoted value as in `project:"Release 2.0"`
+                let mut word = String::new();
+                while i < chars.len() && !chars[i].is_whitespace() && !"()".contains(chars[i]) {
+                    if chars[i] == '"' {
+                        let end = (i + 1..chars.len())
+                            .find(|&j| chars[j] == '"')
+                            .ok_or_else(|| error(i, "Unclosed quote"))?;
+                        word.extend(&chars[i + 1..end]);
+                        i = end + 1;
+                    } else {
+                        word.push(chars[i]);
+                        i += 1;
+                    }
+                }
+
+                if word.eq_ignore_ascii_case("or") {
+                    TokenKind::Or
+                } else {
+                    TokenKind::Word(word)
+                }
+            }
+        };
+        tokens.push(Token { kind, column });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    source: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn error(&self, column: usize, message: impl Into<String>) -> FilterError {
+        FilterError {
+            message: message.into(),
+            column,
+            query: self.source.to_string(),
+        }
+    }
+
+    // or := and ("OR" and)*
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = vec![self.parse_and()?];
+        while let Some(token) = self.peek() {
+            if token.kind != TokenKind::Or {
+                break;
+            }
+            let column = token.column;
+            self.pos += 1;
+            if self.peek().is_none_or(|t| t.kind == TokenKind::Close) {
+                return Err(self.error(column, "Expected a term after OR"));
+            }
+            exprs.push(self.parse_and()?);
+        }
+
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    // and := unary*
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = Vec::new();
+        while let Some(token) = self.peek() {
+            match token.kind {
+                TokenKind::Or if exprs.is_empty() => {
+                    return Err(self.error(token.column, "Expected a term before OR"))
+                }
+                TokenKind::Or | TokenKind::Close => break,
+                _ => exprs.push(self.parse_unary()?),
+            }
+        }
+
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::And(exprs)
+        })
+    }
+
+    // unary := "-" unary | "(" or ")" | term
+    fn parse_unary(&mut self) -> Result<Expr, FilterError> {
+        let Some(token) = self.peek() else {
+            let end = self.source.chars().count();
+            return Err(self.error(end, "Expected a term"));
+        };
+        let column = token.column;
+        self.pos += 1;
+
+        match &token.kind {
+            TokenKind::Not => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            TokenKind::Open => {
+                if let Some(close) = self.peek().filter(|t| t.kind == TokenKind::Close) {
+                    return Err(self.error(close.column, "Expected a term"));
+                }
+                let expr = self.parse_or()?;
+                match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(self.error(column, "Unmatched '('")),
+                }
+            }
+            TokenKind::Phrase(phrase) => Ok(Expr::Text(phrase.clone())),
+            TokenKind::Word(word) => self.parse_word(word, column),
+            TokenKind::Or | TokenKind::Close => Err(self.error(column, "Expected a term")),
+        }
+    }
+
+    fn parse_word(&self, word: &str, column: usize) -> Result<Expr, FilterError> {
+        match word.to_lowercase().as_str() {
+            "done" => return Ok(Expr::Done),
+            "open" => return Ok(Expr::Open),
+            "overdue" => return Ok(Expr::Overdue),
+            _ => {}
+        }
+
+        if let Some(tag) = word.strip_prefix('#') {
+            return self.parse_tag(tag, column);
+        }
+
+        let Some((field, value)) = word.split_once(':') else {
+            return Ok(Expr::Text(word.to_string()));
+        };
+        // points at the value rather than the field name
+        let value_column = column + field.chars().count() + 1;
+        if value.is_empty() {
+            return Err(self.error(value_column, format!("Expected a value for '{}'", field)));
+        }
+
+        match field.to_lowercase().as_str() {
+            "priority" | "p" => Priority::from_str(value)
+                .map(Expr::Priority)
+                .map_err(|e| self.error(value_column, e)),
+            "tag" => self.parse_tag(value, value_column),
+            "project" => Ok(Expr::Project(value.to_string())),
+            "created" => self.parse_date(DateField::Created, value, value_column),
+            "due" if value.eq_ignore_ascii_case("none") => Ok(Expr::NoDueDate),
+            "due" => self.parse_date(DateField::Due, value, value_column),
+            "completed" => self.parse_date(DateField::Completed, value, value_column),
+            _ => Err(self.error(
+                column,
+                format!("Unknown field '{}', expected one of {}", field, FIELDS),
+            )),
+        }
+    }
+
+    fn parse_tag(&self, value: &str, column: usize) -> Result<Expr, FilterError> {
+        match parse_tags(value).as_slice() {
+            [tag] => Ok(Expr::Tag(tag.clone())),
+            _ => Err(self.error(column, "Expected a single tag name")),
+        }
+    }
+
+    fn parse_date(
+        &self,
+        field: DateField,
+        value: &str,
+        column: usize,
+    ) -> Result<Expr, FilterError> {
+        let (comparison, date) = if let Some(date) = value.strip_prefix(">=") {
+            (Comparison::OnOrAfter, date)
+        } else if let Some(date) = value.strip_prefix("<=") {
+            (Comparison::OnOrBefore, date)
+        } else if let Some(date) = value.strip_prefix('>') {
+            (Comparison::After, date)
+        } else if let Some(date) = value.strip_prefix('<') {
+            (Comparison::Before, date)
+        } else {
+            (Comparison::On, value.strip_prefix('=').unwrap_or(value))
+        };
+
+        let offset = value.chars().count() - date.chars().count();
+        let date = match (date.to_lowercase().as_str(), relative_days(date)) {
+            (_, Some(days)) => {
+                // checked against today to reject offsets no date can be
+                // that far from; the date itself is worked out when matching
+                let relative = DateValue::Relative(days);
+                if relative.resolve(Local::now().date_naive()).is_none() {
+                    return Err(self.error(
+                        column + offset,
+                        format!("Date '{}' is too far from today", date),
+                    ));
+                }
+                relative
+            }
+            ("today", _) => DateValue::Relative(0),
+            ("tomorrow", _) => DateValue::Relative(1),
+            ("yesterday", _) => DateValue::Relative(-1),
+            _ => DateValue::Fixed(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                self.error(
+                    column + offset,
+                    format!(
+                        "Invalid date '{}': expected YYYY-MM-DD, today, tomorrow, yesterday or a number of days such as -7d",
+                        date
+                    ),
+                )
+            })?),
+        };
+
+        Ok(Expr::Date(field, comparison, date))
+    }
+}
+
+// a number of days relative to today, such as `-7d` or `+3d`
+fn relative_days(input: &str) -> Option<i64> {
+    let days = input.strip_suffix('d')?;
+    if !days.starts_with(['-', '+']) {
+        return None;
+    }
+    days.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn parse(input: &str) -> Expr {
+        Filter::parse(input).unwrap().expr
+    }
+
+    fn error(input: &str) -> FilterError {
+        Filter::parse(input).unwrap_err()
+    }
+
+    fn text(word: &str) -> Expr {
+        Expr::Text(word.to_string())
+    }
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    fn context_on(projects: &[Project], day: u32) -> FilterContext<'_> {
+        FilterContext {
+            now: Local.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+            projects,
+        }
+    }
+
+    #[test]
+    fn tokenizes_words_phrases_and_operators() {
+        assert_eq!(
+            kinds(r#"-done (a or b) project:"Release 2.0" "login bug""#),
+            [
+                TokenKind::Not,
+                TokenKind::Word(String::from("done")),
+                TokenKind::Open,
+                TokenKind::Word(String::from("a")),
+                TokenKind::Or,
+                TokenKind::Word(String::from("b")),
+                TokenKind::Close,
+                TokenKind::Word(String::from("project:Release 2.0")),
+                TokenKind::Phrase(String::from("login bug")),
+            ]
+        );
+
+        let columns: Vec<usize> = tokenize("  ab (c)")
+            .unwrap()
+            .iter()
+            .map(|token| token.column)
+            .collect();
+        assert_eq!(columns, [2, 5, 6, 7]);
+
+        // a dash on its own is a word, not a negation
+        assert_eq!(kinds("a - b")[1], TokenKind::Word(String::from("-")));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("a b OR c"),
+            Expr::Or(vec![Expr::And(vec![text("a"), text("b")]), text("c")])
+        );
+        assert_eq!(
+            parse("a OR b c"),
+            Expr::Or(vec![text("a"), Expr::And(vec![text("b"), text("c")])])
+        );
+    }
+
+    #[test]
+    fn parentheses_group_terms() {
+        assert_eq!(
+            parse("a (b OR c)"),
+            Expr::And(vec![text("a"), Expr::Or(vec![text("b"), text("c")])])
+        );
+        assert_eq!(parse("((a))"), text("a"));
+    }
+
+    #[test]
+    fn dashes_negate_the_next_term() {
+        assert_eq!(parse("-done"), Expr::Not(Box::new(Expr::Done)));
+        assert_eq!(
+            parse("--open"),
+            Expr::Not(Box::new(Expr::Not(Box::new(Expr::Open))))
+        );
+        assert_eq!(
+            parse("-(a OR b)"),
+            Expr::Not(Box::new(Expr::Or(vec![text("a"), text("b")])))
+        );
+    }
+
+    #[test]
+    fn quotes_keep_spaces_in_phrases_and_values() {
+        assert_eq!(parse(r#""login bug""#), text("login bug"));
+        assert_eq!(
+            parse(r#"project:"Release 2.0""#),
+            Expr::Project(String::from("Release 2.0"))
+        );
+        assert_eq!(parse(r#""a OR b""#), text("a OR b"));
+    }
+
+    #[test]
+    fn parses_fields() {
+        assert_eq!(parse("P:HIGH"), Expr::Priority(Priority::High));
+        assert_eq!(parse("#Infra"), Expr::Tag(String::from("infra")));
+        assert_eq!(parse("tag:infra"), Expr::Tag(String::from("infra")));
+        assert_eq!(parse("due:none"), Expr::NoDueDate);
+        assert_eq!(
+            parse("created:>2026-01-01"),
+            Expr::Date(
+                DateField::Created,
+                Comparison::After,
+                DateValue::Fixed(NaiveDate::from_ymd_opt(2026, 1, 1).unwrap())
+            )
+        );
+        assert_eq!(
+            parse("due:<=today"),
+            Expr::Date(
+                DateField::Due,
+                Comparison::OnOrBefore,
+                DateValue::Relative(0)
+            )
+        );
+        assert_eq!(
+            parse("completed:>=-7d"),
+            Expr::Date(
+                DateField::Completed,
+                Comparison::OnOrAfter,
+                DateValue::Relative(-7)
+            )
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let cases = [
+            (r#"a "unclosed"#, 2, "Unclosed quote"),
+            ("a)", 1, "Unmatched ')'"),
+            ("(a", 0, "Unmatched '('"),
+            ("OR a", 0, "Expected a term before OR"),
+            ("a OR", 2, "Expected a term after OR"),
+            ("a -)", 3, "Expected a term"),
+            ("()", 1, "Expected a term"),
+            ("size:big", 0, "Unknown field 'size'"),
+            ("tag:", 4, "Expected a value for 'tag'"),
+            ("tag:a,b", 4, "Expected a single tag name"),
+            ("due:>tomorow", 5, "Invalid date 'tomorow'"),
+        ];
+        for (input, column, message) in cases {
+            let error = error(input);
+            assert!(
+                error.message.starts_with(message),
+                "{}: {}",
+                input,
+                error.message
+            );
+            assert_eq!(error.column, column, "{}", input);
+        }
+    }
+
+    #[test]
+    fn relative_dates_out_of_range_are_errors() {
+        for input in [
+            "due:>+99999999d",
+            "due:>+999999999999999d",
+            "created:-99999999d",
+        ] {
+            assert!(
+                error(input).message.contains("too far from today"),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn relative_dates_follow_the_time_of_matching() {
+        let filter = Filter::parse("due:today").unwrap();
+        let mut todo = Todo::new(String::from("Pay rent"));
+        todo.due_date = NaiveDate::from_ymd_opt(2026, 3, 2);
+
+        assert!(filter.matches(&todo, &context_on(&[], 2)));
+        assert!(!filter.matches(&todo, &context_on(&[], 3)));
+
+        let filter = Filter::parse("due:>=-1d").unwrap();
+        assert!(filter.matches(&todo, &context_on(&[], 3)));
+        assert!(!filter.matches(&todo, &context_on(&[], 4)));
+    }
+
+    #[test]
+    fn matches_todos() {
+        let projects = [Project {
+            id: 7,
+            name: String::from("Release 2.0"),
+            created_at: Local::now(),
+            archived_at: None,
+        }];
+        let context = context_on(&projects, 1);
+
+        let mut todo = Todo::new(String::from("Fix the Login bug"));
+        todo.priority = Some(Priority::High);
+        todo.tags = vec![String::from("infra")];
+        todo.project_id = 7;
+
+        let matches = |query: &str| Filter::parse(query).unwrap().matches(&todo, &context);
+        assert!(matches("login -done"));
+        assert!(matches(r#"priority:high #infra project:"release 2.0""#));
+        assert!(matches("priority:low OR tag:infra"));
+        assert!(!matches("done OR priority:low"));
+        assert!(!matches("project:Other"));
+        assert!(matches("due:none"));
+    }
+}
@@ -1,2 +1,4 @@
-pub mod color_scheme;
+pub mod filter;
+pub mod project;
 pub mod todo;
+pub mod view;
@@ -0,0 +1,14 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// The project every todo belongs to unless moved elsewhere. It is created by
+/// the schema migration that introduced projects and cannot be archived.
+pub const DEFAULT_PROJECT_ID: i64 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub created_at: DateTime<Local>,
+    pub archived_at: Option<DateTime<Local>>,
+}
@@ -1,6 +1,15 @@
-use chrono::{DateTime, Local};
-use std::fmt::Display;
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use super::project::DEFAULT_PROJECT_ID;
+use std::{fmt::Display, ops::Range, str::FromStr};
+
+/// Version of the JSON representation of a `Todo`. Bump this whenever a
+/// field is renamed, removed or changes meaning; adding fields is compatible.
+pub const JSON_FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Priority {
     Low,
     Medium,
@@ -17,7 +26,23 @@ impl Display for Priority {
     }
 }
 
-#[derive(Clone)]
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "high" | "h" | "1" => Ok(Priority::High),
+            "medium" | "m" | "2" => Ok(Priority::Medium),
+            "low" | "l" | "3" => Ok(Priority::Low),
+            _ => Err(format!(
+                "Invalid priority '{}': expected high, medium or low",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Todo {
     pub id: i64,
     pub text: String,
@@ -25,6 +50,39 @@ pub struct Todo {
     pub created_at: DateTime<Local>,
     pub completed_at: Option<DateTime<Local>>,
     pub priority: Option<Priority>,
+    pub due_date: Option<NaiveDate>,
+    pub due_time: Option<NaiveTime>,
+    pub deleted_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_project_id")]
+    pub project_id: i64,
+    /// The todo this is a subtask of
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// Free-form, possibly multi-line, description
+    #[serde(default)]
+    pub notes: String,
+    /// Where the todo goes when sorted by hand; lower comes first
+    #[serde(default)]
+    pub position: i64,
+}
+
+fn default_project_id() -> i64 {
+    DEFAULT_PROJECT_ID
+}
+
+/// A todo found by a full-text search, with an excerpt of the matching text
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub todo: Todo,
+    pub snippet: String,
+    /// Byte ranges of the matched words within `snippet`
+    #[serde(skip)]
+    pub matches: Vec<Range<usize>>,
+    /// Relevance as computed by SQLite's bm25, lower is better
+    pub rank: f64,
 }
 
 impl Todo {
@@ -36,6 +94,97 @@ impl Todo {
             created_at: Local::now(),
             completed_at: None,
             priority: None,
+            due_date: None,
+            due_time: None,
+            deleted_at: None,
+            tags: Vec::new(),
+            project_id: DEFAULT_PROJECT_ID,
+            parent_id: None,
+            notes: String::new(),
+            position: 0,
+        }
+    }
+
+    /// An open todo is overdue once its due date (and time, if set) has passed
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        if self.completed {
+            return false;
+        }
+
+        match (self.due_date, self.due_time) {
+            (Some(date), Some(time)) => date.and_time(time) < now.naive_local(),
+            (Some(date), None) => date < now.date_naive(),
+            _ => false,
         }
     }
+
+    /// An open todo is due today if its due date is today and it isn't overdue yet
+    pub fn is_due_today(&self, now: DateTime<Local>) -> bool {
+        !self.completed && self.due_date == Some(now.date_naive()) && !self.is_overdue(now)
+    }
+
+    /// Formats the due date for display, e.g. `2026-01-31 17:00`
+    pub fn due_display(&self) -> String {
+        format_due(self.due_date, self.due_time)
+    }
+}
+
+pub fn format_due(date: Option<NaiveDate>, time: Option<NaiveTime>) -> String {
+    match (date, time) {
+        (Some(date), Some(time)) => format!("{} {}", date.format("%Y-%m-%d"), time.format("%H:%M")),
+        (Some(date), None) => date.format("%Y-%m-%d").to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Parses a due date typed by the user.
+///
+/// Accepts an empty string (no due date), `YYYY-MM-DD`, `YYYY-MM-DD HH:MM`,
+/// and the shorthands `today` and `tomorrow` (optionally followed by a time).
+pub fn parse_due(input: &str) -> Result<(Option<NaiveDate>, Option<NaiveTime>), String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok((None, None));
+    }
+
+    let mut parts = input.split_whitespace();
+    let date_part = parts.next().unwrap_or_default();
+    let time_part = parts.next();
+    if parts.next().is_some() {
+        return Err(format!(
+            "Invalid due date '{}': expected YYYY-MM-DD [HH:MM]",
+            input
+        ));
+    }
+
+    let today = Local::now().date_naive();
+    let date = match date_part.to_lowercase().as_str() {
+        "today" => today,
+        "tomorrow" => today + Days::new(1),
+        _ => NaiveDate::parse_from_str(date_part, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid due date '{}': expected YYYY-MM-DD", date_part))?,
+    };
+
+    let time = match time_part {
+        Some(time) => Some(
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| format!("Invalid due time '{}': expected HH:MM", time))?,
+        ),
+        None => None,
+    };
+
+    Ok((Some(date), time))
+}
+
+/// Splits user input such as `frontend, #infra ops` into normalised tag names:
+/// lowercase, without a leading `#`, sorted and without duplicates.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| tag.trim().trim_start_matches('#').to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
 }
@@ -0,0 +1,301 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::todo::Todo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortCriteria {
+    Priority,
+    Completed,
+    CreatedDate,
+    DueDate,
+    /// When todos were completed
+    CompletedDate,
+    /// The order todos were put in by hand
+    Manual,
+}
+
+impl SortCriteria {
+    /// The name used when storing a sort in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortCriteria::Priority => "priority",
+            SortCriteria::Completed => "status",
+            SortCriteria::CreatedDate => "created",
+            SortCriteria::DueDate => "due",
+            SortCriteria::CompletedDate => "completed",
+            SortCriteria::Manual => "manual",
+        }
+    }
+}
+
+impl Display for SortCriteria {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SortCriteria {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "priority" => Ok(SortCriteria::Priority),
+            "status" => Ok(SortCriteria::Completed),
+            "created" => Ok(SortCriteria::CreatedDate),
+            "due" => Ok(SortCriteria::DueDate),
+            "completed" => Ok(SortCriteria::CompletedDate),
+            "manual" => Ok(SortCriteria::Manual),
+            _ => Err(format!(
+                "Invalid sort '{}': expected priority, status, created, due, completed or manual",
+                s
+            )),
+        }
+    }
+}
+
+impl SortCriteria {
+    /// Compares two todos in ascending order of this criterion
+    fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        match self {
+            SortCriteria::Priority => a.priority.cmp(&b.priority),
+            SortCriteria::Completed => a.completed.cmp(&b.completed),
+            SortCriteria::CreatedDate => a.created_at.cmp(&b.created_at),
+            SortCriteria::DueDate => {
+                let a_due = a.due_date.map(|d| (d, a.due_time));
+                let b_due = b.due_date.map(|d| (d, b.due_time));
+                a_due.cmp(&b_due)
+            }
+            SortCriteria::CompletedDate => a.completed_at.cmp(&b.completed_at),
+            SortCriteria::Manual => a.position.cmp(&b.position),
+        }
+    }
+}
+
+/// One key of a sort specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub criteria: SortCriteria,
+    pub ascending: bool,
+}
+
+impl SortKey {
+    pub fn arrow(&self) -> &'static str {
+        if self.ascending {
+            "↑"
+        } else {
+            "↓"
+        }
+    }
+
+    fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        // todos without a due or completion date always go last, whatever
+        // the direction
+        let dated = match self.criteria {
+            SortCriteria::DueDate => Some((a.due_date.is_some(), b.due_date.is_some())),
+            SortCriteria::CompletedDate => {
+                Some((a.completed_at.is_some(), b.completed_at.is_some()))
+            }
+            _ => None,
+        };
+        match dated {
+            Some((true, false)) => return Ordering::Less,
+            Some((false, true)) => return Ordering::Greater,
+            _ => {}
+        }
+
+        let ordering = self.criteria.compare(a, b);
+        if self.ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+/// An ordered list of sort keys: todos are ordered by the first key, ties by
+/// the second and so on, with any remaining ties left in creation order.
+///
+/// Written as a comma separated list of criteria, each descending if it
+/// starts with `-`, e.g. `status, -priority, created`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SortSpec {
+    keys: Vec<SortKey>,
+}
+
+impl SortSpec {
+    pub fn new(criteria: SortCriteria, ascending: bool) -> Self {
+        Self {
+            keys: vec![SortKey {
+                criteria,
+                ascending,
+            }],
+        }
+    }
+
+    pub fn keys(&self) -> &[SortKey] {
+        &self.keys
+    }
+
+    /// The key for `criteria` and its position in the spec, if it is one
+    pub fn find(&self, criteria: SortCriteria) -> Option<(usize, &SortKey)> {
+        self.keys
+            .iter()
+            .enumerate()
+            .find(|(_, key)| key.criteria == criteria)
+    }
+
+    /// Makes `criteria` the first key, keeping the others behind it. If it
+    /// already is the first key its direction is reversed instead.
+    pub fn promote(&mut self, criteria: SortCriteria) {
+        match self.find(criteria) {
+            Some((0, _)) => self.reverse_first(),
+            _ => {
+                self.keys.retain(|key| key.criteria != criteria);
+                self.keys.insert(
+                    0,
+                    SortKey {
+                        criteria,
+                        ascending: true,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn reverse_first(&mut self) {
+        if let Some(key) = self.keys.first_mut() {
+            key.ascending = !key.ascending;
+        }
+    }
+
+    pub fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        self.keys
+            .iter()
+            .map(|key| key.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.id.cmp(&b.id))
+    }
+}
+
+impl Default for SortSpec {
+    fn default() -> Self {
+        Self::new(SortCriteria::Priority, true)
+    }
+}
+
+impl Display for SortSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .map(|key| format!("{}{}", if key.ascending { "" } else { "-" }, key.criteria))
+            .collect();
+        write!(f, "{}", keys.join(", "))
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys: Vec<SortKey> = Vec::new();
+        for word in s.split([',', ' ']).filter(|w| !w.is_empty()) {
+            let (ascending, name) = match word.strip_prefix('-') {
+                Some(name) => (false, name),
+                None => (true, word.strip_prefix('+').unwrap_or(word)),
+            };
+            let criteria: SortCriteria = name.parse()?;
+            if keys.iter().any(|key| key.criteria == criteria) {
+                return Err(format!("'{}' is sorted by more than once", criteria));
+            }
+            keys.push(SortKey {
+                criteria,
+                ascending,
+            });
+        }
+
+        if keys.is_empty() {
+            return Err(String::from("A sort needs at least one key"));
+        }
+        Ok(Self { keys })
+    }
+}
+
+impl TryFrom<String> for SortSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SortSpec> for String {
+    fn from(sort: SortSpec) -> Self {
+        sort.to_string()
+    }
+}
+
+/// A named combination of a filter query and a sort order
+#[derive(Debug, Clone)]
+pub struct View {
+    /// `None` for the built-in views, which aren't stored in the database
+    pub id: Option<i64>,
+    pub name: String,
+    /// A query in the filter language, empty to show everything
+    pub filter: String,
+    pub sort: SortSpec,
+}
+
+impl View {
+    pub fn is_builtin(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
+/// The views every database has, listed before the saved ones
+pub fn builtin_views() -> Vec<View> {
+    let builtin = |name: &str, filter: &str, sort: &str| View {
+        id: None,
+        name: name.to_string(),
+        filter: filter.to_string(),
+        sort: sort.parse().expect("built-in sorts are valid"),
+    };
+
+    vec![
+        builtin("All", "", "priority"),
+        builtin("Today", "open due:<=today", "due, -priority"),
+        builtin("High priority open", "open priority:high", "due, created"),
+        builtin("Recently completed", "done completed:>=-7d", "-completed"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Local};
+
+    use super::*;
+
+    #[test]
+    fn completed_sorts_by_completion_date_with_open_todos_last() {
+        let now = Local::now();
+        let todo = |text: &str, completed_at: Option<DateTime<Local>>| Todo {
+            completed: completed_at.is_some(),
+            completed_at,
+            ..Todo::new(text.to_string())
+        };
+        let mut todos = [
+            todo("open", None),
+            todo("last week", Some(now - Duration::days(7))),
+            todo("today", Some(now)),
+        ];
+
+        let sort: SortSpec = "-completed".parse().unwrap();
+        todos.sort_by(|a, b| sort.compare(a, b));
+        let texts: Vec<&str> = todos.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["today", "last week", "open"]);
+        assert_eq!(sort.to_string(), "-completed");
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::models::todo::Todo;
+
+// oldest steps are dropped once the history grows past this
+const MAX_STEPS: usize = 100;
+
+/// A single change made to a todo, holding enough state to reverse it
+#[derive(Clone)]
+pub enum Change {
+    Created(Todo),
+    Deleted(Todo),
+    /// a todo brought back from the trash
+    Restored(Todo),
+    Updated {
+        before: Todo,
+        after: Todo,
+    },
+}
+
+impl Change {
+    /// id of the todo the change was made to
+    pub fn todo_id(&self) -> i64 {
+        match self {
+            Change::Created(todo) | Change::Deleted(todo) | Change::Restored(todo) => todo.id,
+            Change::Updated { after, .. } => after.id,
+        }
+    }
+}
+
+/// One undoable user operation, which may touch several todos
+pub struct Step {
+    pub changes: Vec<Change>,
+    /// id of the todo in focus before the operation, focused again when it's undone
+    pub focus_id: Option<i64>,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Records a new operation, discarding anything that could be redone
+    pub fn record(&mut self, step: Step) {
+        if step.changes.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push(step);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Step> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Step> {
+        self.redo.pop()
+    }
+
+    pub fn push_undo(&mut self, step: Step) {
+        self.undo.push(step);
+    }
+
+    pub fn push_redo(&mut self, step: Step) {
+        self.redo.push(step);
+    }
+
+    /// Moves the todos in every recorded step to their renumbered positions,
+    /// so undoing a step doesn't bring back a position from before
+    pub fn reposition(&mut self, positions: &HashMap<i64, i64>) {
+        for todo in self.todos_mut() {
+            if let Some(&position) = positions.get(&todo.id) {
+                todo.position = position;
+            }
+        }
+    }
+
+    /// Makes the recorded subtasks of the todos with `parent_ids` top-level,
+    /// so undoing a step doesn't point them at a parent that was purged
+    pub fn detach(&mut self, parent_ids: &[i64]) {
+        for todo in self.todos_mut() {
+            if todo.parent_id.is_some_and(|id| parent_ids.contains(&id)) {
+                todo.parent_id = None;
+            }
+        }
+    }
+
+    // every version of a todo held by a recorded step
+    fn todos_mut(&mut self) -> impl Iterator<Item = &mut Todo> {
+        let steps = self.undo.iter_mut().chain(self.redo.iter_mut());
+        steps
+            .flat_map(|step| step.changes.iter_mut())
+            .flat_map(|change| match change {
+                Change::Created(todo) | Change::Deleted(todo) | Change::Restored(todo) => {
+                    vec![todo]
+                }
+                Change::Updated { before, after } => vec![before, after],
+            })
+    }
+
+    /// Drops every step that touches one of `ids`, for todos that are gone for good
+    pub fn forget(&mut self, ids: &[i64]) {
+        let touches = |step: &Step| {
+            step.changes
+                .iter()
+                .any(|change| ids.contains(&change.todo_id()))
+        };
+        self.undo.retain(|step| !touches(step));
+        self.redo.retain(|step| !touches(step));
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
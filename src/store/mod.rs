@@ -0,0 +1,3 @@
+pub mod history;
+pub mod query;
+pub mod todo_store;
@@ -0,0 +1,28 @@
+use crate::models::{
+    filter::{Filter, FilterContext},
+    todo::Todo,
+};
+
+/// Which todos to list. Every todo matches the default query; each field
+/// that is set narrows it down further.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Only todos in the project with this id
+    pub project: Option<i64>,
+    /// Only todos carrying at least one of these tags
+    pub tags: Vec<String>,
+    /// Only todos matching this filter, e.g. `priority:high -done`
+    pub filter: Option<Filter>,
+}
+
+impl Query {
+    /// Whether `todo` is one of the todos this query lists
+    pub fn matches(&self, todo: &Todo, context: &FilterContext) -> bool {
+        self.project.is_none_or(|id| todo.project_id == id)
+            && (self.tags.is_empty() || todo.tags.iter().any(|t| self.tags.contains(t)))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|f| f.matches(todo, context))
+    }
+}
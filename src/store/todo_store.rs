@@ -0,0 +1,717 @@
+use std::collections::HashMap;
+
+use chrono::{Days, Local, NaiveDate, NaiveTime};
+use thiserror::Error;
+
+use super::{
+    history::{Change, History, Step},
+    query::Query,
+};
+use crate::{
+    db::handler::{DatabaseError, DatabaseHandler},
+    models::{
+        filter::FilterContext,
+        project::{Project, DEFAULT_PROJECT_ID},
+        todo::{Priority, SearchHit, Todo},
+        view::{SortSpec, View},
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+    #[error("No todo with id {0}")]
+    NoSuchTodo(i64),
+    #[error("A todo needs some text")]
+    EmptyText,
+    #[error("No project with id {0}")]
+    NoSuchProject(i64),
+    #[error("Project name cannot be empty")]
+    EmptyProjectName,
+    #[error("A project called '{0}' already exists")]
+    DuplicateProject(String),
+    #[error("The default project cannot be archived")]
+    ArchiveDefaultProject,
+    #[error("Number of days is out of range")]
+    DaysOutOfRange,
+}
+
+/// The user-editable fields of a todo
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoFields {
+    pub text: String,
+    /// None for a todo without a priority, like those from before priorities
+    pub priority: Option<Priority>,
+    pub due_date: Option<NaiveDate>,
+    pub due_time: Option<NaiveTime>,
+    pub tags: Vec<String>,
+    pub notes: String,
+}
+
+impl TodoFields {
+    /// Fields for a todo with just `text`, at medium priority
+    pub fn new(text: impl Into<String>) -> Self {
+        TodoFields {
+            text: text.into(),
+            priority: Some(Priority::Medium),
+            due_date: None,
+            due_time: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        }
+    }
+
+    /// Checks the fields make a valid todo, which needs some text
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.text.trim().is_empty() {
+            return Err(StoreError::EmptyText);
+        }
+        Ok(())
+    }
+
+    fn apply(&self, todo: &mut Todo) {
+        todo.text = self.text.clone();
+        todo.priority = self.priority;
+        todo.due_date = self.due_date;
+        todo.due_time = self.due_time;
+        todo.tags = self.tags.clone();
+        todo.notes = self.notes.clone();
+    }
+}
+
+/// The todos and projects of a database, held in memory. Every change is
+/// written to the database straight away and can be undone.
+///
+/// ```
+/// use r_todo::{Query, TodoFields, TodoStore};
+///
+/// let mut store = TodoStore::open(":memory:")?;
+/// let id = store.add(TodoFields::new("Buy milk"), r_todo::DEFAULT_PROJECT_ID)?;
+/// store.complete(id)?;
+///
+/// let done = Query {
+///     filter: Some(r_todo::Filter::parse("done")?),
+///     ..Query::default()
+/// };
+/// assert_eq!(store.query(&done).len(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TodoStore {
+    db: DatabaseHandler,
+    /// Todos that haven't been deleted
+    todos: Vec<Todo>,
+    /// Every tag in use
+    tags: Vec<String>,
+    /// Projects that haven't been archived
+    projects: Vec<Project>,
+    history: History,
+    /// The todo to come back to when the next change is undone
+    focus: Option<i64>,
+}
+
+impl TodoStore {
+    /// Opens the database at `path`, creating it and bringing its schema up
+    /// to date as needed. `":memory:"` opens a fresh database in memory.
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        let db = DatabaseHandler::new(path)?;
+        Ok(TodoStore {
+            todos: db.load_todos()?,
+            tags: db.load_tag_names()?,
+            projects: db.load_projects()?,
+            history: History::default(),
+            focus: None,
+            db,
+        })
+    }
+
+    /// Todos that haven't been deleted, in the order of the last `sort` with
+    /// any added since at the end
+    pub fn todos(&self) -> &[Todo] {
+        &self.todos
+    }
+
+    pub fn get(&self, id: i64) -> Option<&Todo> {
+        self.todos.iter().find(|t| t.id == id)
+    }
+
+    /// Finds the position of a todo in `todos()` by its database id
+    pub fn index_of(&self, id: i64) -> Option<usize> {
+        self.todos.iter().position(|t| t.id == id)
+    }
+
+    /// The todos matching `query`, in the same order as `todos()`
+    pub fn query(&self, query: &Query) -> Vec<&Todo> {
+        let context = self.filter_context();
+        self.todos
+            .iter()
+            .filter(|todo| query.matches(todo, &context))
+            .collect()
+    }
+
+    /// What a filter needs to know to be matched against the todos right now
+    pub fn filter_context(&self) -> FilterContext<'_> {
+        FilterContext {
+            now: Local::now(),
+            projects: &self.projects,
+        }
+    }
+
+    /// Full-text search of the text and notes of every todo, best matches
+    /// first. Deleted todos and those in archived projects are only searched
+    /// if `include_archived` is set.
+    pub fn search(
+        &self,
+        query: &str,
+        include_archived: bool,
+    ) -> Result<Vec<SearchHit>, StoreError> {
+        Ok(self.db.search(query, include_archived)?)
+    }
+
+    pub fn sort(&mut self, sort: &SortSpec) {
+        self.todos.sort_by(|a, b| sort.compare(a, b));
+    }
+
+    /// Every tag in use, in alphabetical order
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Ids of all subtasks of a todo, their subtasks, and so on
+    pub fn descendant_ids(&self, id: i64) -> Vec<i64> {
+        let mut descendants = Vec::new();
+        let mut pending = vec![id];
+        while let Some(parent) = pending.pop() {
+            for todo in self.todos.iter().filter(|t| t.parent_id == Some(parent)) {
+                // guard against cycles in corrupted data
+                if todo.id != id && !descendants.contains(&todo.id) {
+                    descendants.push(todo.id);
+                    pending.push(todo.id);
+                }
+            }
+        }
+        descendants
+    }
+
+    /// Adds a todo to the project with id `project_id`, returning its id
+    pub fn add(&mut self, fields: TodoFields, project_id: i64) -> Result<i64, StoreError> {
+        if !self.projects.iter().any(|p| p.id == project_id) {
+            return Err(StoreError::NoSuchProject(project_id));
+        }
+        self.insert(fields, project_id, None)
+    }
+
+    /// Adds a todo as a subtask of the todo with id `parent_id`, in the same
+    /// project, returning its id
+    pub fn add_subtask(&mut self, parent_id: i64, fields: TodoFields) -> Result<i64, StoreError> {
+        let project_id = self
+            .get(parent_id)
+            .ok_or(StoreError::NoSuchTodo(parent_id))?
+            .project_id;
+        self.insert(fields, project_id, Some(parent_id))
+    }
+
+    fn insert(
+        &mut self,
+        fields: TodoFields,
+        project_id: i64,
+        parent_id: Option<i64>,
+    ) -> Result<i64, StoreError> {
+        fields.validate()?;
+        let mut todo = Todo::new(String::new());
+        fields.apply(&mut todo);
+        todo.project_id = project_id;
+        todo.parent_id = parent_id;
+        todo.position = self.db.next_position()?;
+
+        todo.id = self.db.insert_todo(&todo)?;
+        self.record(vec![Change::Created(todo.clone())]);
+        self.todos.push(todo.clone());
+        self.refresh_tags()?;
+        Ok(todo.id)
+    }
+
+    /// Replaces the text, priority, due date, tags and notes of a todo
+    pub fn update(&mut self, id: i64, fields: TodoFields) -> Result<(), StoreError> {
+        self.find(id)?;
+        fields.validate()?;
+        self.update_many(&[id], |todo| fields.apply(todo))?;
+        self.refresh_tags()?;
+        Ok(())
+    }
+
+    /// Marks a todo as completed along with all of its open subtasks. Does
+    /// nothing if it already is.
+    pub fn complete(&mut self, id: i64) -> Result<(), StoreError> {
+        if self.todos[self.find(id)?].completed {
+            return Ok(());
+        }
+
+        let mut ids = vec![id];
+        ids.extend(
+            self.descendant_ids(id)
+                .into_iter()
+                .filter(|&id| self.get(id).is_some_and(|t| !t.completed)),
+        );
+        let completed_at = Some(Local::now());
+        self.update_many(&ids, |todo| {
+            todo.completed = true;
+            todo.completed_at = completed_at;
+        })
+    }
+
+    /// Marks a completed todo as open again, leaving its subtasks as they are
+    pub fn reopen(&mut self, id: i64) -> Result<(), StoreError> {
+        if !self.todos[self.find(id)?].completed {
+            return Ok(());
+        }
+
+        self.update_many(&[id], |todo| {
+            todo.completed = false;
+            todo.completed_at = None;
+        })
+    }
+
+    /// Moves a todo and all of its subtasks to the trash
+    pub fn delete(&mut self, id: i64) -> Result<(), StoreError> {
+        self.find(id)?;
+        let mut ids = vec![id];
+        ids.extend(self.descendant_ids(id));
+        self.delete_many(&ids)
+    }
+
+    /// Moves every completed todo to the trash. A completed todo that still
+    /// has open subtasks is kept, so clearing never throws away open work.
+    pub fn clear_completed(&mut self) -> Result<(), StoreError> {
+        let ids: Vec<i64> = self
+            .todos
+            .iter()
+            .filter(|t| t.completed)
+            .filter(|t| {
+                self.descendant_ids(t.id)
+                    .iter()
+                    .all(|&id| self.get(id).is_none_or(|t| t.completed))
+            })
+            .map(|t| t.id)
+            .collect();
+        self.delete_many(&ids)
+    }
+
+    /// Moves a todo and its subtasks to the project with id `project_id`.
+    /// Subtasks always live in the same project as their parent, so a moved
+    /// subtask is detached from the parent it leaves behind.
+    pub fn move_to_project(&mut self, id: i64, project_id: i64) -> Result<(), StoreError> {
+        if self.todos[self.find(id)?].project_id == project_id {
+            return Ok(());
+        }
+        if !self.projects.iter().any(|p| p.id == project_id) {
+            return Err(StoreError::NoSuchProject(project_id));
+        }
+
+        let mut ids = vec![id];
+        ids.extend(self.descendant_ids(id));
+        self.update_many(&ids, |todo| {
+            todo.project_id = project_id;
+            if todo.id == id {
+                todo.parent_id = None;
+            }
+        })
+    }
+
+    /// Moves a todo to `position` in the order todos are sorted in by hand
+    pub fn set_position(&mut self, id: i64, position: i64) -> Result<(), StoreError> {
+        self.find(id)?;
+        self.update_many(&[id], |todo| todo.position = position)
+    }
+
+    /// Spreads the positions of every todo, deleted ones included, evenly
+    /// again, returning the new position of each todo by id
+    pub fn renumber_positions(&mut self) -> Result<HashMap<i64, i64>, StoreError> {
+        let positions = self.db.renumber_positions()?;
+        for todo in self.todos.iter_mut() {
+            if let Some(&position) = positions.get(&todo.id) {
+                todo.position = position;
+            }
+        }
+        self.history.reposition(&positions);
+        Ok(positions)
+    }
+
+    /// Applies `update` to each of the todos with the given ids as a single undo step
+    fn update_many(&mut self, ids: &[i64], update: impl Fn(&mut Todo)) -> Result<(), StoreError> {
+        let mut changes = Vec::new();
+        let mut result = Ok(());
+
+        for &id in ids {
+            let Some(index) = self.index_of(id) else {
+                continue;
+            };
+
+            let todo = &mut self.todos[index];
+            let before = todo.clone();
+            update(todo);
+            if let Err(e) = self.db.update_todo(todo) {
+                // keep whatever was already updated undoable
+                *todo = before;
+                result = Err(e);
+                break;
+            }
+            changes.push(Change::Updated {
+                before,
+                after: todo.clone(),
+            });
+        }
+
+        self.record(changes);
+        result.map_err(StoreError::from)
+    }
+
+    /// Moves the todos with the given ids to the trash as a single undo step
+    fn delete_many(&mut self, ids: &[i64]) -> Result<(), StoreError> {
+        let mut changes = Vec::new();
+        let mut result = Ok(());
+
+        for &id in ids {
+            let Some(index) = self.index_of(id) else {
+                continue;
+            };
+
+            // keep whatever was already deleted undoable
+            if let Err(e) = self.db.delete_todo(id) {
+                result = Err(e);
+                break;
+            }
+            changes.push(Change::Deleted(self.todos.remove(index)));
+        }
+
+        self.record(changes);
+        result.map_err(StoreError::from)
+    }
+
+    fn find(&self, id: i64) -> Result<usize, StoreError> {
+        self.index_of(id).ok_or(StoreError::NoSuchTodo(id))
+    }
+
+    fn record(&mut self, changes: Vec<Change>) {
+        self.history.record(Step {
+            changes,
+            focus_id: self.focus,
+        });
+    }
+
+    fn refresh_tags(&mut self) -> Result<(), DatabaseError> {
+        self.tags = self.db.load_tag_names()?;
+        Ok(())
+    }
+
+    /// The todo a front end had in focus. Each change records it, and undoing
+    /// or redoing a change moves the focus to the todo it was made to.
+    pub fn focus(&self) -> Option<i64> {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, id: Option<i64>) {
+        self.focus = id;
+    }
+
+    /// Reverts the most recent change. Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> Result<bool, StoreError> {
+        let Some(step) = self.history.pop_undo() else {
+            return Ok(false);
+        };
+
+        for (i, change) in step.changes.iter().enumerate().rev() {
+            let result = match change {
+                Change::Created(todo) => self.erase_todo(todo),
+                Change::Deleted(todo) => self.restore_todo(todo),
+                Change::Restored(todo) => self.remove_todo(todo),
+                Change::Updated { before, .. } => self.replace_todo(before),
+            };
+
+            if let Err(e) = result {
+                // the changes after this one have already been undone, so only
+                // the ones before it can still be undone
+                self.history.push_undo(Step {
+                    changes: step.changes[..=i].to_vec(),
+                    focus_id: step.focus_id,
+                });
+                return Err(e.into());
+            }
+        }
+
+        self.focus = step.focus_id;
+        self.history.push_redo(step);
+        self.refresh_tags()?;
+        Ok(true)
+    }
+
+    /// Re-applies the most recently undone change. Returns false if there was nothing to redo.
+    pub fn redo(&mut self) -> Result<bool, StoreError> {
+        let Some(step) = self.history.pop_redo() else {
+            return Ok(false);
+        };
+
+        for (i, change) in step.changes.iter().enumerate() {
+            let result = match change {
+                Change::Created(todo) => self.reinsert_todo(todo),
+                Change::Deleted(todo) => self.remove_todo(todo),
+                Change::Restored(todo) => self.restore_todo(todo),
+                Change::Updated { after, .. } => self.replace_todo(after),
+            };
+
+            if let Err(e) = result {
+                self.history.push_redo(Step {
+                    changes: step.changes[i..].to_vec(),
+                    focus_id: step.focus_id,
+                });
+                return Err(e.into());
+            }
+        }
+
+        self.focus = step.changes.last().map(Change::todo_id);
+        self.history.push_undo(step);
+        self.refresh_tags()?;
+        Ok(true)
+    }
+
+    // removes an added todo for good, so undoing an add doesn't fill the
+    // trash, without recording history
+    fn erase_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
+        self.db.erase_todo(todo.id)?;
+        self.todos.retain(|t| t.id != todo.id);
+        Ok(())
+    }
+
+    // puts an erased todo back under its old id without recording history
+    fn reinsert_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
+        self.db.reinsert_todo(todo)?;
+        self.todos.push(todo.clone());
+        Ok(())
+    }
+
+    // soft-deletes a todo without recording history
+    fn remove_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
+        self.db.delete_todo(todo.id)?;
+        self.todos.retain(|t| t.id != todo.id);
+        Ok(())
+    }
+
+    // brings a soft-deleted todo back without recording history
+    fn restore_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
+        self.db.restore_todo(todo.id)?;
+        let mut todo = todo.clone();
+        todo.deleted_at = None;
+        self.todos.retain(|t| t.id != todo.id);
+        self.todos.push(todo);
+        Ok(())
+    }
+
+    // overwrites a todo with a previous or later version without recording history
+    fn replace_todo(&mut self, todo: &Todo) -> Result<(), DatabaseError> {
+        self.db.update_todo(todo)?;
+        if let Some(existing) = self.todos.iter_mut().find(|t| t.id == todo.id) {
+            *existing = todo.clone();
+        }
+        Ok(())
+    }
+
+    /// The todos in the trash, most recently deleted first
+    pub fn deleted(&self) -> Result<Vec<Todo>, StoreError> {
+        Ok(self.db.load_deleted_todos()?)
+    }
+
+    /// Moves a todo from the trash back among the others, along with the
+    /// subtasks that were trashed with it, as a single undo step
+    pub fn restore(&mut self, id: i64) -> Result<(), StoreError> {
+        let trash = self.db.load_deleted_todos()?;
+        let todo = trash
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(StoreError::NoSuchTodo(id))?;
+
+        // subtasks deleted before their parent were trashed on their own
+        let mut todos = vec![todo];
+        todos.extend(trashed_subtasks(&trash, id, |t| {
+            t.deleted_at >= todo.deleted_at
+        }));
+
+        let mut changes = Vec::new();
+        let mut result = Ok(());
+        for todo in todos {
+            // keep whatever was already restored undoable
+            if let Err(e) = self.db.restore_todo(todo.id) {
+                result = Err(e);
+                break;
+            }
+            let mut todo = todo.clone();
+            todo.deleted_at = None;
+            changes.push(Change::Restored(todo.clone()));
+            self.todos.push(todo);
+        }
+
+        self.record(changes);
+        self.refresh_tags()?;
+        result.map_err(StoreError::from)
+    }
+
+    /// Permanently deletes a todo from the trash, along with its subtasks
+    /// that are in the trash too
+    pub fn purge(&mut self, id: i64) -> Result<(), StoreError> {
+        let trash = self.db.load_deleted_todos()?;
+        if !trash.iter().any(|t| t.id == id) {
+            return Err(StoreError::NoSuchTodo(id));
+        }
+
+        let mut ids = vec![id];
+        ids.extend(trashed_subtasks(&trash, id, |_| true).iter().map(|t| t.id));
+        if self.db.purge_todos(&ids)? == 0 {
+            return Err(StoreError::NoSuchTodo(id));
+        }
+        self.forget_purged(&ids);
+        Ok(())
+    }
+
+    /// Permanently deletes everything that has been in the trash for more
+    /// than `days` days, returning how many todos were deleted
+    pub fn purge_older_than(&mut self, days: u64) -> Result<usize, StoreError> {
+        let cutoff = Local::now()
+            .checked_sub_days(Days::new(days))
+            .ok_or(StoreError::DaysOutOfRange)?;
+        let purged = self.db.purge_deleted_before(cutoff)?;
+        self.forget_purged(&purged);
+        Ok(purged.len())
+    }
+
+    // drops the history of purged todos, which can no longer be undone or
+    // redone, and detaches any subtasks they leave behind like the database does
+    fn forget_purged(&mut self, ids: &[i64]) {
+        self.history.forget(ids);
+        self.history.detach(ids);
+        for todo in &mut self.todos {
+            if todo.parent_id.is_some_and(|id| ids.contains(&id)) {
+                todo.parent_id = None;
+            }
+        }
+    }
+
+    /// Projects that haven't been archived, oldest first
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    pub fn find_project(&self, name: &str) -> Option<&Project> {
+        self.projects
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Creates a project called `name`, returning its id
+    pub fn create_project(&mut self, name: &str) -> Result<i64, StoreError> {
+        let name = validate_project_name(name)?;
+        if self.find_project(&name).is_some() {
+            return Err(StoreError::DuplicateProject(name));
+        }
+
+        let mut project = Project {
+            id: 0,
+            name,
+            created_at: Local::now(),
+            archived_at: None,
+        };
+        project.id = self.db.insert_project(&project)?;
+        self.projects.push(project.clone());
+        Ok(project.id)
+    }
+
+    pub fn rename_project(&mut self, id: i64, name: &str) -> Result<(), StoreError> {
+        if !self.projects.iter().any(|p| p.id == id) {
+            return Err(StoreError::NoSuchProject(id));
+        }
+        let name = validate_project_name(name)?;
+        if self.find_project(&name).is_some_and(|p| p.id != id) {
+            return Err(StoreError::DuplicateProject(name));
+        }
+
+        self.db.rename_project(id, &name)?;
+        if let Some(project) = self.projects.iter_mut().find(|p| p.id == id) {
+            project.name = name;
+        }
+        Ok(())
+    }
+
+    /// Archives a project, hiding it and its todos. The default project
+    /// can't be archived.
+    pub fn archive_project(&mut self, id: i64) -> Result<(), StoreError> {
+        if id == DEFAULT_PROJECT_ID {
+            return Err(StoreError::ArchiveDefaultProject);
+        }
+        if !self.projects.iter().any(|p| p.id == id) {
+            return Err(StoreError::NoSuchProject(id));
+        }
+
+        self.db.archive_project(id)?;
+        self.projects.retain(|p| p.id != id);
+
+        let hidden: Vec<i64> = self
+            .todos
+            .iter()
+            .filter(|t| t.project_id == id)
+            .map(|t| t.id)
+            .collect();
+        self.todos.retain(|t| t.project_id != id);
+        // undoing a change to a hidden todo would bring it back into view
+        self.history.forget(&hidden);
+        Ok(())
+    }
+
+    /// The saved views, oldest first
+    pub fn views(&self) -> Result<Vec<View>, StoreError> {
+        Ok(self.db.load_views()?)
+    }
+
+    /// Saves a view, replacing any saved view of the same name, and returns its id
+    pub fn save_view(&mut self, view: &View) -> Result<i64, StoreError> {
+        Ok(self.db.save_view(view)?)
+    }
+
+    pub fn delete_view(&mut self, id: i64) -> Result<(), StoreError> {
+        Ok(self.db.delete_view(id)?)
+    }
+
+    pub fn setting(&self, key: &str) -> Result<Option<String>, StoreError> {
+        Ok(self.db.get_setting(key)?)
+    }
+
+    pub fn set_setting(&mut self, key: &str, value: &str) -> Result<(), StoreError> {
+        Ok(self.db.set_setting(key, value)?)
+    }
+
+    pub fn delete_setting(&mut self, key: &str) -> Result<(), StoreError> {
+        Ok(self.db.delete_setting(key)?)
+    }
+}
+
+fn validate_project_name(name: &str) -> Result<String, StoreError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(StoreError::EmptyProjectName);
+    }
+    Ok(name.to_string())
+}
+
+// the todos in `trash` below the one with id `id`, not looking past any
+// subtask `keep` turns down
+fn trashed_subtasks(trash: &[Todo], id: i64, keep: impl Fn(&Todo) -> bool) -> Vec<&Todo> {
+    let mut subtasks: Vec<&Todo> = Vec::new();
+    let mut pending = vec![id];
+    while let Some(parent) = pending.pop() {
+        for todo in trash.iter().filter(|t| t.parent_id == Some(parent)) {
+            // guard against cycles in corrupted data
+            if todo.id != id && !subtasks.iter().any(|t| t.id == todo.id) && keep(todo) {
+                subtasks.push(todo);
+                pending.push(todo.id);
+            }
+        }
+    }
+    subtasks
+}
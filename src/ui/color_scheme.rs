@@ -0,0 +1,327 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+use super::color_support::ColorSupport;
+
+/// The colours of the UI. Theme files set any of the fields as hex colours,
+/// e.g. `accent = "#0078D4"`; fields left out keep the default dark colour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorScheme {
+    // background colors
+    pub bg: Color,
+    pub bg_dark: Color,
+    pub bg_darker: Color,
+
+    // foreground colors
+    pub fg: Color,
+    pub fg_light: Color,
+    pub fg_dark: Color,
+
+    // UI elements
+    pub border: Color,
+    pub accent: Color,
+    pub error: Color,
+
+    // task status colors
+    pub completed: Color,
+    pub pending: Color,
+    pub in_progress: Color,
+
+    // due date colors
+    pub overdue: Color,
+    pub due_today: Color,
+
+    // search matches
+    pub search_match: Color,
+
+    // selection and interactive elements
+    pub selection: Color,
+    pub dropdown_bg: Color,
+
+    // inactive elements
+    pub inactive: Color,
+
+    /// Set when there are no colours, so the styles fall back on bold,
+    /// dim, underlined and reversed text
+    #[serde(skip)]
+    pub monochrome: bool,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            // Background colors
+            bg: Color::Rgb(31, 31, 31),        // #1F1F1F
+            bg_dark: Color::Rgb(24, 24, 24),   // #181818
+            bg_darker: Color::Rgb(32, 32, 32), // #202020
+
+            // Foreground colors
+            fg: Color::Rgb(204, 204, 204),       // #CCCCCC
+            fg_light: Color::Rgb(255, 255, 255), // #FFFFFF
+            fg_dark: Color::Rgb(157, 157, 157),  // #9D9D9D
+
+            // UI elements
+            border: Color::Rgb(43, 43, 43),  // #2B2B2B
+            accent: Color::Rgb(0, 120, 212), // #0078D4
+            error: Color::Rgb(248, 81, 73),  // #F85149
+
+            // Task status colors
+            completed: Color::Rgb(46, 160, 67),   // #2EA043
+            pending: Color::Rgb(110, 118, 129),   // #6E7681
+            in_progress: Color::Rgb(0, 120, 212), // #0078D4
+
+            // Due date colors
+            overdue: Color::Rgb(248, 81, 73),    // #F85149
+            due_today: Color::Rgb(210, 153, 34), // #D29922
+
+            // Search matches
+            search_match: Color::Rgb(210, 153, 34), // #D29922
+
+            // Selection and interactive elements
+            selection: Color::Rgb(21, 85, 194),  // #1555c2
+            dropdown_bg: Color::Rgb(49, 49, 49), // #313131
+
+            // Inactive elements
+            inactive: Color::Rgb(110, 118, 129), // #6E7681
+
+            monochrome: false,
+        }
+    }
+}
+
+impl ColorScheme {
+    pub fn light() -> Self {
+        Self {
+            bg: Color::Rgb(255, 255, 255),        // #FFFFFF
+            bg_dark: Color::Rgb(243, 243, 243),   // #F3F3F3
+            bg_darker: Color::Rgb(235, 235, 235), // #EBEBEB
+
+            fg: Color::Rgb(31, 35, 40),         // #1F2328
+            fg_light: Color::Rgb(0, 0, 0),      // #000000
+            fg_dark: Color::Rgb(101, 109, 118), // #656D76
+
+            border: Color::Rgb(208, 215, 222), // #D0D7DE
+            accent: Color::Rgb(9, 105, 218),   // #0969DA
+            error: Color::Rgb(207, 34, 46),    // #CF222E
+
+            completed: Color::Rgb(26, 127, 55),   // #1A7F37
+            pending: Color::Rgb(110, 119, 129),   // #6E7781
+            in_progress: Color::Rgb(9, 105, 218), // #0969DA
+
+            overdue: Color::Rgb(207, 34, 46),   // #CF222E
+            due_today: Color::Rgb(154, 103, 0), // #9A6700
+
+            search_match: Color::Rgb(154, 103, 0), // #9A6700
+
+            selection: Color::Rgb(9, 105, 218),     // #0969DA
+            dropdown_bg: Color::Rgb(234, 238, 242), // #EAEEF2
+
+            inactive: Color::Rgb(140, 149, 159), // #8C959F
+
+            monochrome: false,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            bg: Color::Rgb(0, 0, 0),        // #000000
+            bg_dark: Color::Rgb(0, 0, 0),   // #000000
+            bg_darker: Color::Rgb(0, 0, 0), // #000000
+
+            fg: Color::Rgb(255, 255, 255),       // #FFFFFF
+            fg_light: Color::Rgb(255, 255, 255), // #FFFFFF
+            fg_dark: Color::Rgb(192, 192, 192),  // #C0C0C0
+
+            border: Color::Rgb(255, 255, 255), // #FFFFFF
+            accent: Color::Rgb(0, 255, 255),   // #00FFFF
+            error: Color::Rgb(255, 0, 0),      // #FF0000
+
+            completed: Color::Rgb(0, 255, 0),     // #00FF00
+            pending: Color::Rgb(192, 192, 192),   // #C0C0C0
+            in_progress: Color::Rgb(0, 255, 255), // #00FFFF
+
+            overdue: Color::Rgb(255, 0, 0),     // #FF0000
+            due_today: Color::Rgb(255, 255, 0), // #FFFF00
+
+            search_match: Color::Rgb(255, 255, 0), // #FFFF00
+
+            selection: Color::Rgb(255, 0, 255),  // #FF00FF
+            dropdown_bg: Color::Rgb(26, 26, 26), // #1A1A1A
+
+            inactive: Color::Rgb(128, 128, 128), // #808080
+
+            monochrome: false,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized, dark variant
+    pub fn solarized() -> Self {
+        Self {
+            bg: Color::Rgb(0, 43, 54),        // #002B36
+            bg_dark: Color::Rgb(0, 36, 46),   // #00242E
+            bg_darker: Color::Rgb(7, 54, 66), // #073642
+
+            fg: Color::Rgb(131, 148, 150),       // #839496
+            fg_light: Color::Rgb(147, 161, 161), // #93A1A1
+            fg_dark: Color::Rgb(88, 110, 117),   // #586E75
+
+            border: Color::Rgb(7, 54, 66),    // #073642
+            accent: Color::Rgb(38, 139, 210), // #268BD2
+            error: Color::Rgb(220, 50, 47),   // #DC322F
+
+            completed: Color::Rgb(133, 153, 0),    // #859900
+            pending: Color::Rgb(88, 110, 117),     // #586E75
+            in_progress: Color::Rgb(38, 139, 210), // #268BD2
+
+            overdue: Color::Rgb(220, 50, 47),   // #DC322F
+            due_today: Color::Rgb(181, 137, 0), // #B58900
+
+            search_match: Color::Rgb(181, 137, 0), // #B58900
+
+            selection: Color::Rgb(108, 113, 196), // #6C71C4
+            dropdown_bg: Color::Rgb(7, 54, 66),   // #073642
+
+            inactive: Color::Rgb(88, 110, 117), // #586E75
+
+            monochrome: false,
+        }
+    }
+
+    /// Reads a theme file, see `ColorScheme` for the format
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// The scheme as close as the terminal can show it
+    pub fn adapt(mut self, support: ColorSupport) -> Self {
+        for color in [
+            &mut self.bg,
+            &mut self.bg_dark,
+            &mut self.bg_darker,
+            &mut self.fg,
+            &mut self.fg_light,
+            &mut self.fg_dark,
+            &mut self.border,
+            &mut self.accent,
+            &mut self.error,
+            &mut self.completed,
+            &mut self.pending,
+            &mut self.in_progress,
+            &mut self.overdue,
+            &mut self.due_today,
+            &mut self.search_match,
+            &mut self.selection,
+            &mut self.dropdown_bg,
+            &mut self.inactive,
+        ] {
+            *color = support.downsample(*color);
+        }
+        self.monochrome = support == ColorSupport::Monochrome;
+        self
+    }
+
+    // adds `modifier` when there are no colours to tell the style apart
+    fn or_mono(&self, style: Style, modifier: Modifier) -> Style {
+        if self.monochrome {
+            style.add_modifier(modifier)
+        } else {
+            style
+        }
+    }
+}
+
+/// A colour scheme and the name it is picked by
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub colors: ColorScheme,
+}
+
+/// The themes every install has, before any loaded from theme files
+pub fn builtin_themes() -> Vec<Theme> {
+    let theme = |name: &str, colors| Theme {
+        name: name.to_string(),
+        colors,
+    };
+
+    vec![
+        theme("dark", ColorScheme::default()),
+        theme("light", ColorScheme::light()),
+        theme("high-contrast", ColorScheme::high_contrast()),
+        theme("solarized", ColorScheme::solarized()),
+    ]
+}
+
+impl ColorScheme {
+    /// Style for highlighted text
+    pub fn highlighted_text(&self) -> Style {
+        Style::default().fg(self.fg_light).bg(self.bg)
+    }
+
+    /// Style for secondary text
+    pub fn secondary_text(&self) -> Style {
+        self.or_mono(Style::default().fg(self.fg_dark).bg(self.bg), Modifier::DIM)
+    }
+
+    /// Style for selected borders
+    pub fn selected_border(&self) -> Style {
+        self.or_mono(Style::default().fg(self.accent), Modifier::BOLD)
+    }
+
+    /// Style for active titles
+    pub fn title(&self) -> Style {
+        self.or_mono(Style::default().fg(self.fg_light), Modifier::BOLD)
+    }
+
+    /// Style for selected items
+    pub fn selected(&self) -> Style {
+        self.or_mono(
+            Style::default().fg(self.fg_light).bg(self.selection),
+            Modifier::REVERSED,
+        )
+    }
+
+    /// Style for the selected row of a table or list
+    pub fn highlighted_row(&self) -> Style {
+        self.or_mono(Style::default().fg(self.selection), Modifier::REVERSED)
+    }
+
+    /// Style for the text of the focused field of a form
+    pub fn focused_field(&self) -> Style {
+        self.or_mono(Style::default().fg(self.selection), Modifier::BOLD)
+    }
+
+    /// Style for the chosen one of several options
+    pub fn chosen(&self) -> Style {
+        self.or_mono(Style::default().fg(self.due_today), Modifier::REVERSED)
+    }
+
+    /// Style for completed tasks
+    pub fn completed_task(&self) -> Style {
+        self.or_mono(Style::default().fg(self.completed), Modifier::DIM)
+    }
+
+    /// Style for overdue tasks
+    pub fn overdue_task(&self) -> Style {
+        self.or_mono(Style::default().fg(self.overdue), Modifier::BOLD)
+    }
+
+    /// Style for tasks due today
+    pub fn due_today_task(&self) -> Style {
+        self.or_mono(Style::default().fg(self.due_today), Modifier::UNDERLINED)
+    }
+
+    /// Style for the parts of a task that match the search
+    pub fn search_match(&self) -> Style {
+        self.or_mono(
+            Style::default().fg(self.bg).bg(self.search_match),
+            Modifier::REVERSED,
+        )
+    }
+
+    /// Style for errors
+    pub fn error(&self) -> Style {
+        self.or_mono(Style::default().fg(self.error), Modifier::BOLD)
+    }
+}
@@ -0,0 +1,212 @@
+use std::env;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// How many colours the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorSupport {
+    /// Work it out from `NO_COLOR`, `COLORTERM` and `TERM`
+    #[serde(rename = "auto")]
+    Auto,
+    /// 24-bit colour, used as is
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    /// The xterm 256 colour palette
+    #[serde(rename = "256")]
+    Ansi256,
+    /// The 16 basic ANSI colours, as configured in the terminal
+    #[serde(rename = "16")]
+    Ansi16,
+    /// No colour, only bold, dim, underlined and reversed text
+    #[serde(rename = "mono")]
+    Monochrome,
+}
+
+// the usual xterm values of the 16 basic colours, to find the closest one
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// the levels of each channel in the 6x6x6 colour cube of the 256 palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    /// Replaces `Auto` with what the environment says the terminal supports
+    pub fn resolve(self) -> ColorSupport {
+        match self {
+            ColorSupport::Auto => detect(
+                env::var("NO_COLOR").ok().as_deref(),
+                env::var("COLORTERM").ok().as_deref(),
+                env::var("TERM").ok().as_deref(),
+            ),
+            support => support,
+        }
+    }
+
+    /// The closest colour to `color` that can be shown
+    pub fn downsample(&self, color: Color) -> Color {
+        let Color::Rgb(r, g, b) = color else {
+            return match self {
+                ColorSupport::Monochrome => Color::Reset,
+                _ => color,
+            };
+        };
+
+        match self {
+            ColorSupport::Auto | ColorSupport::TrueColor => color,
+            ColorSupport::Ansi256 => Color::Indexed(to_ansi256(r, g, b)),
+            ColorSupport::Ansi16 => to_ansi16(r, g, b),
+            ColorSupport::Monochrome => Color::Reset,
+        }
+    }
+}
+
+/// Follows https://no-color.org: any non-empty `NO_COLOR` turns colour off.
+/// Otherwise `COLORTERM` announces 24-bit colour and `TERM` names the rest.
+pub fn detect(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> ColorSupport {
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        return ColorSupport::Monochrome;
+    }
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return ColorSupport::TrueColor;
+    }
+
+    match term.unwrap_or_default() {
+        "dumb" => ColorSupport::Monochrome,
+        term if term.contains("truecolor") || term.contains("direct") => ColorSupport::TrueColor,
+        term if term.contains("256color") => ColorSupport::Ansi256,
+        _ => ColorSupport::Ansi16,
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+// the closer of the nearest colour cube entry and the nearest grey
+fn to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| -> usize {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &level)| (level as i32 - v as i32).abs())
+            .map(|(i, _)| i)
+            .unwrap_or_default()
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    // 24 greys from 8 to 238 in steps of 10
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let grey_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey_level = 8 + 10 * grey_step;
+    let grey = (grey_level, grey_level, grey_level);
+
+    if distance((r, g, b), grey) < distance((r, g, b), cube) {
+        232 + grey_step
+    } else {
+        cube_index as u8
+    }
+}
+
+fn to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map(|&(color, _)| color)
+        .unwrap_or(Color::Reset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_color_turns_colour_off_whatever_the_terminal() {
+        assert_eq!(
+            detect(Some("1"), Some("truecolor"), Some("xterm-256color")),
+            ColorSupport::Monochrome
+        );
+        // an empty NO_COLOR doesn't count
+        assert_eq!(
+            detect(Some(""), None, Some("xterm-256color")),
+            ColorSupport::Ansi256
+        );
+    }
+
+    #[test]
+    fn colorterm_and_term_name_the_colours() {
+        assert_eq!(
+            detect(None, Some("truecolor"), Some("xterm")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(detect(None, Some("24bit"), None), ColorSupport::TrueColor);
+        assert_eq!(
+            detect(None, None, Some("xterm-direct")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, Some("screen-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(detect(None, None, Some("xterm")), ColorSupport::Ansi16);
+        assert_eq!(detect(None, None, None), ColorSupport::Ansi16);
+        assert_eq!(detect(None, None, Some("dumb")), ColorSupport::Monochrome);
+    }
+
+    #[test]
+    fn downsamples_to_the_256_colour_palette() {
+        let downsample = |r, g, b| ColorSupport::Ansi256.downsample(Color::Rgb(r, g, b));
+        // exact cube entries
+        assert_eq!(downsample(0, 0, 0), Color::Indexed(16));
+        assert_eq!(downsample(255, 0, 0), Color::Indexed(196));
+        assert_eq!(downsample(95, 135, 175), Color::Indexed(67));
+        // greys are closer to the grey ramp than to the cube
+        assert_eq!(downsample(128, 128, 128), Color::Indexed(244));
+        assert_eq!(downsample(238, 238, 238), Color::Indexed(255));
+    }
+
+    #[test]
+    fn downsamples_to_the_nearest_of_16_colours() {
+        let downsample = |r, g, b| ColorSupport::Ansi16.downsample(Color::Rgb(r, g, b));
+        assert_eq!(downsample(250, 10, 10), Color::LightRed);
+        assert_eq!(downsample(180, 20, 20), Color::Red);
+        assert_eq!(downsample(120, 120, 130), Color::DarkGray);
+        assert_eq!(downsample(20, 20, 200), Color::Blue);
+        assert_eq!(downsample(0x00, 0x78, 0xD4), Color::Cyan);
+    }
+
+    #[test]
+    fn only_rgb_colours_are_changed_unless_colour_is_off() {
+        assert_eq!(ColorSupport::Ansi16.downsample(Color::Cyan), Color::Cyan);
+        assert_eq!(
+            ColorSupport::Ansi256.downsample(Color::Indexed(42)),
+            Color::Indexed(42)
+        );
+        let rgb = Color::Rgb(1, 2, 3);
+        assert_eq!(ColorSupport::TrueColor.downsample(rgb), rgb);
+        assert_eq!(ColorSupport::Monochrome.downsample(rgb), Color::Reset);
+        assert_eq!(
+            ColorSupport::Monochrome.downsample(Color::Red),
+            Color::Reset
+        );
+    }
+}
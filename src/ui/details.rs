@@ -0,0 +1,51 @@
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::state::App;
+
+/// Shows the notes and other details of the selected todo
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let colors = &app.colors;
+
+    let mut lines = Vec::new();
+    if let Some(todo) = app.selected_index().map(|i| &app.store.todos()[i]) {
+        lines.push(Line::styled(todo.text.as_str(), colors.title()));
+        lines.push(Line::from(""));
+
+        let mut detail = |label: &'static str, value: String| {
+            lines.push(Line::from(vec![
+                Span::styled(label, colors.secondary_text()),
+                Span::raw(value),
+            ]));
+        };
+        detail(
+            "Created: ",
+            todo.created_at.format("%Y-%m-%d %H:%M").to_string(),
+        );
+        if todo.due_date.is_some() {
+            detail("Due:     ", todo.due_display());
+        }
+        if !todo.tags.is_empty() {
+            detail("Tags:    ", todo.tags.join(", "));
+        }
+
+        if !todo.notes.is_empty() {
+            lines.push(Line::from(""));
+            lines.extend(todo.notes.lines().map(Line::from));
+        }
+    }
+
+    let details = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title("Details")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1)),
+    );
+
+    frame.render_widget(details, area);
+}
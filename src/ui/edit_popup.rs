@@ -1,43 +1,85 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Style, Stylize},
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph},
+    text::Span,
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem},
     Frame,
 };
 
+use super::{line_input::LineInput, text_area::TextArea};
+use crate::app::state::Mode;
 use crate::{app::state::App, models::todo::Priority};
-use crate::{app::state::Mode, models::color_scheme::ColorScheme};
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SelectableField {
     Text,
     Priority,
+    Due,
+    Tags,
+    Notes,
 }
 
 pub struct EditingState {
     pub input_fields: InputFields,
     pub selected_field: Option<SelectableField>,
+    /// Set when creating a subtask of this todo
+    pub parent_id: Option<i64>,
+    /// The row selected before a new todo was started, selected again once
+    /// it's saved
+    pub last_selected: usize,
 }
 
 pub struct InputFields {
-    pub text: String,
+    pub text: LineInput,
     pub priority: Option<Priority>,
+    pub due: LineInput,
+    pub tags: LineInput,
+    pub notes: TextArea,
 }
 
-pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let colors = ColorScheme::default();
+impl InputFields {
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.priority = None;
+        self.due.clear();
+        self.tags.clear();
+        self.notes.clear();
+    }
+}
+
+impl EditingState {
+    /// The single line input that currently has focus, if any
+    pub fn focused_input(&mut self) -> Option<&mut LineInput> {
+        match self.selected_field {
+            Some(SelectableField::Text) => Some(&mut self.input_fields.text),
+            Some(SelectableField::Due) => Some(&mut self.input_fields.due),
+            Some(SelectableField::Tags) => Some(&mut self.input_fields.tags),
+            _ => None,
+        }
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
+    let colors = &app.colors;
 
     match app.mode {
-        Mode::Normal => return,
+        Mode::Normal
+        | Mode::Trash
+        | Mode::Prompt
+        | Mode::Search
+        | Mode::SearchResults
+        | Mode::Views => {}
         Mode::Editing => {
             let is_create = app.editing_state.input_fields.text.is_empty();
-            let title = if is_create {
+            let title = if app.editing_state.parent_id.is_some() {
+                "Create Subtask"
+            } else if is_create {
                 "Create ToDo"
             } else {
                 "Edit ToDo"
             };
 
-            let popup_area = centered_rect(60, 30, area);
+            let popup_area = centered_rect(60, 70, area);
 
             // clear the popup area
             frame.render_widget(Clear, popup_area);
@@ -57,30 +99,46 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
                 .constraints([
                     Constraint::Length(3), // text input
                     Constraint::Length(5), // priority input
+                    Constraint::Length(3), // due date input
+                    Constraint::Length(3), // tags input
+                    Constraint::Min(5),    // notes input
                 ])
                 .split(popup_area);
 
             // render the outer block
             frame.render_widget(popup, popup_area);
 
+            let fields = [
+                SelectableField::Text,
+                SelectableField::Priority,
+                SelectableField::Due,
+                SelectableField::Tags,
+                SelectableField::Notes,
+            ];
+            app.layout.fields = fields.into_iter().zip(chunks.iter().copied()).collect();
+
             // render the text input
-            let text_style = match app.editing_state.selected_field {
-                Some(SelectableField::Text) => Style::default().fg(colors.selection),
-                _ => Style::default(),
+            let text_selected = app.editing_state.selected_field == Some(SelectableField::Text);
+            let text_style = if text_selected {
+                colors.focused_field()
+            } else {
+                Style::default()
             };
 
-            let text_input = Paragraph::new(app.editing_state.input_fields.text.as_str())
-                .style(text_style)
-                .block(
-                    Block::default()
-                        .title("Todo")
-                        .borders(Borders::ALL)
-                        .border_type(BorderType::Rounded),
-                );
-            frame.render_widget(text_input, chunks[0]);
+            app.editing_state.input_fields.text.render(
+                frame,
+                chunks[0],
+                Block::default()
+                    .title("Todo")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+                text_style,
+                None,
+                text_selected,
+            );
 
             // render the priority input
-            let priorities = vec![Priority::High, Priority::Medium, Priority::Low];
+            let priorities = [Priority::High, Priority::Medium, Priority::Low];
             let priority_items: Vec<ListItem> = priorities
                 .iter()
                 .map(|p| {
@@ -88,7 +146,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
                         == Some(SelectableField::Priority)
                         && Some(*p) == app.editing_state.input_fields.priority
                     {
-                        Style::default().yellow()
+                        colors.chosen()
                     } else {
                         Style::default()
                     };
@@ -114,6 +172,81 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
                 .highlight_style(Style::default().reversed());
 
             frame.render_widget(priority_list, chunks[1]);
+
+            // one line per priority inside the field's border
+            let priority_area = chunks[1].inner(Margin::new(1, 1));
+            app.layout.priorities = priorities
+                .iter()
+                .zip(priority_area.rows())
+                .map(|(&priority, line)| (priority, line))
+                .collect();
+
+            // render the due date input
+            let due_selected = app.editing_state.selected_field == Some(SelectableField::Due);
+            let due_style = if due_selected {
+                colors.focused_field()
+            } else {
+                Style::default()
+            };
+
+            app.editing_state.input_fields.due.render(
+                frame,
+                chunks[2],
+                Block::default()
+                    .title("Due (YYYY-MM-DD [HH:MM])")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+                due_style,
+                None,
+                due_selected,
+            );
+
+            // render the tags input, with the autocomplete suggestion greyed out
+            let tags_selected = app.editing_state.selected_field == Some(SelectableField::Tags);
+            let tags_style = if tags_selected {
+                colors.focused_field()
+            } else {
+                Style::default()
+            };
+
+            let tags = &app.editing_state.input_fields.tags;
+            let suggestion = if tags_selected && tags.cursor_at_end() {
+                app.suggest_tag(tags.value())
+                    .map(|s| Span::styled(s.to_string(), colors.secondary_text()))
+            } else {
+                None
+            };
+
+            app.editing_state.input_fields.tags.render(
+                frame,
+                chunks[3],
+                Block::default()
+                    .title("Tags (comma separated)")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+                tags_style,
+                suggestion,
+                tags_selected,
+            );
+
+            // render the notes input
+            let notes_selected = app.editing_state.selected_field == Some(SelectableField::Notes);
+            let notes_block = Block::default()
+                .title("Notes")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(if notes_selected {
+                    colors.selected_border()
+                } else {
+                    Style::default()
+                });
+            app.editing_state.input_fields.notes.render(
+                frame,
+                chunks[4],
+                notes_block,
+                Style::default(),
+                notes_selected,
+            );
         }
     }
 }
@@ -1,6 +1,5 @@
 use ratatui::{
-    layout::Rect,
-    style::{Color, Style},
+    layout::{Constraint, Layout, Rect},
     text::Line,
     widgets::{Block, BorderType, Borders, Padding, Paragraph},
     Frame,
@@ -8,31 +7,59 @@ use ratatui::{
 
 use crate::app::state::App;
 
+// the height of the panel when it shows an error
+const ERROR_HEIGHT: u16 = 11;
+// space between the columns of the help
+const COLUMN_GAP: u16 = 3;
+
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let info_text = if app.show_help {
-        app.get_help_text()
+    if !app.show_help && app.error_message.is_none() {
+        return;
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::left(1));
+
+    if app.show_help {
+        let block = block.title(format!("Help - {}", app.key_context().title()));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let lines = app.get_help_text();
+        let (columns, rows) = help_columns(&lines, inner.width);
+        let areas = Layout::horizontal(vec![Constraint::Fill(1); columns])
+            .spacing(COLUMN_GAP)
+            .split(inner);
+        for (column, area) in lines.chunks(rows.max(1)).zip(areas.iter()) {
+            frame.render_widget(Paragraph::new(column.to_vec()), *area);
+        }
     } else if let Some(error) = &app.error_message {
-        error.iter().map(|e| Line::from(e.as_str())).collect()
-    } else {
-        vec![Line::from(String::new())]
-    };
+        let lines: Vec<Line> = error.iter().map(|e| Line::from(e.as_str())).collect();
+        let info = Paragraph::new(lines)
+            .style(app.colors.error())
+            .block(block.title("Error"));
+        frame.render_widget(info, area);
+    }
+}
 
-    let info_style = if app.show_help {
-        Style::default()
+/// How tall the panel needs to be to show the help or the error across
+/// `width` columns of the screen
+pub fn height(app: &App, width: u16) -> u16 {
+    if app.show_help {
+        // the borders and the left padding
+        let (_, rows) = help_columns(&app.get_help_text(), width.saturating_sub(3));
+        rows as u16 + 2
     } else {
-        Style::default().fg(Color::Red)
-    };
-
-    let info_title = if app.show_help { "Help" } else { "Error" };
-
-    if app.show_help || app.error_message.is_some() {
-        let info = Paragraph::new(info_text).style(info_style).block(
-            Block::default()
-                .title(info_title)
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .padding(Padding::left(1)),
-        );
-        frame.render_widget(info, area);
+        ERROR_HEIGHT
     }
 }
+
+// as many columns as the longest line allows, and the rows they take
+fn help_columns(lines: &[Line], width: u16) -> (usize, usize) {
+    let widest = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+    let columns = ((width + COLUMN_GAP) / (widest + COLUMN_GAP)).max(1) as usize;
+    let rows = lines.len().div_ceil(columns);
+    (columns, rows)
+}
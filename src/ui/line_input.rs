@@ -0,0 +1,287 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A single line text input with a cursor. The cursor always sits on a
+/// grapheme boundary, so combined characters and emoji move as one unit.
+#[derive(Default)]
+pub struct LineInput {
+    value: String,
+    /// cursor position, as a byte index into `value`
+    cursor: usize,
+    /// display columns scrolled off the left edge
+    scroll: usize,
+}
+
+impl LineInput {
+    /// Creates an input holding `text`, with the cursor at the end
+    pub fn new(text: &str) -> Self {
+        Self {
+            value: text.to_string(),
+            cursor: text.len(),
+            scroll: 0,
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn cursor_at_end(&self) -> bool {
+        self.cursor == self.value.len()
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.value.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Inserts pasted text, flattening line breaks into spaces
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+        self.value.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    pub fn backspace(&mut self) {
+        let start = prev_grapheme(&self.value, self.cursor);
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn delete(&mut self) {
+        let end = next_grapheme(&self.value, self.cursor);
+        self.value.replace_range(self.cursor..end, "");
+    }
+
+    pub fn delete_word_before(&mut self) {
+        let start = prev_word(&self.value, self.cursor);
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = prev_grapheme(&self.value, self.cursor);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = next_grapheme(&self.value, self.cursor);
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = prev_word(&self.value, self.cursor);
+    }
+
+    pub fn move_word_right(&mut self) {
+        self.cursor = next_word(&self.value, self.cursor);
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.value.len();
+    }
+
+    /// Applies an editing key, returning false if the key isn't one the
+    /// input handles
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Char('w') if ctrl => self.delete_word_before(),
+            KeyCode::Char('b') if alt => self.move_word_left(),
+            KeyCode::Char('f') if alt => self.move_word_right(),
+            KeyCode::Backspace if alt => self.delete_word_before(),
+            KeyCode::Left if ctrl || alt => self.move_word_left(),
+            KeyCode::Right if ctrl || alt => self.move_word_right(),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_char(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Renders the value inside `block`, followed by an optional greyed out
+    /// `hint`, placing the terminal cursor if `focused`
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        style: Style,
+        hint: Option<Span>,
+        focused: bool,
+    ) {
+        let inner = block.inner(area);
+        let width = inner.width as usize;
+        let cursor_x = self.value[..self.cursor].width();
+
+        // keep the cursor on screen, leaving a column for it past the text
+        if cursor_x < self.scroll {
+            self.scroll = cursor_x;
+        } else if width > 0 && cursor_x >= self.scroll + width {
+            self.scroll = cursor_x + 1 - width;
+        }
+
+        let mut spans = vec![Span::styled(self.value.as_str(), style)];
+        spans.extend(hint);
+
+        let input = Paragraph::new(Line::from(spans))
+            .scroll((0, self.scroll as u16))
+            .block(block);
+        frame.render_widget(input, area);
+
+        if focused && width > 0 {
+            frame.set_cursor_position(Position::new(
+                inner.x + (cursor_x - self.scroll) as u16,
+                inner.y,
+            ));
+        }
+    }
+}
+
+/// Byte index of the grapheme boundary before `i`
+pub fn prev_grapheme(s: &str, i: usize) -> usize {
+    s[..i]
+        .grapheme_indices(true)
+        .next_back()
+        .map(|(start, _)| start)
+        .unwrap_or(0)
+}
+
+/// Byte index of the grapheme boundary after `i`
+pub fn next_grapheme(s: &str, i: usize) -> usize {
+    s[i..]
+        .graphemes(true)
+        .next()
+        .map(|g| i + g.len())
+        .unwrap_or(s.len())
+}
+
+/// Start of the word before `i`, skipping any whitespace in between
+pub fn prev_word(s: &str, i: usize) -> usize {
+    let mut in_word = false;
+    for (start, g) in s[..i].grapheme_indices(true).rev() {
+        let space = g.trim().is_empty();
+        if in_word && space {
+            return start + g.len();
+        }
+        in_word |= !space;
+    }
+    0
+}
+
+/// End of the word after `i`, skipping any whitespace in between
+pub fn next_word(s: &str, i: usize) -> usize {
+    let mut in_word = false;
+    for (start, g) in s[i..].grapheme_indices(true) {
+        let space = g.trim().is_empty();
+        if in_word && space {
+            return i + start;
+        }
+        in_word |= !space;
+    }
+    s.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_and_deletes_whole_graphemes() {
+        // "e" with a combining accent, and a thumbs up with a skin tone
+        let mut input = LineInput::new("ae\u{301}\u{1F44D}\u{1F3FD}b");
+        input.move_left();
+        input.move_left();
+        assert_eq!(&input.value()[input.cursor..], "\u{1F44D}\u{1F3FD}b");
+
+        input.backspace();
+        assert_eq!(input.value(), "a\u{1F44D}\u{1F3FD}b");
+        input.delete();
+        assert_eq!(input.value(), "ab");
+
+        input.move_right();
+        assert!(input.cursor_at_end());
+    }
+
+    #[test]
+    fn moves_by_word_over_surrounding_whitespace() {
+        let mut input = LineInput::new("  fix  the bug ");
+        input.move_word_left();
+        assert_eq!(&input.value()[input.cursor..], "bug ");
+        input.move_word_left();
+        input.move_word_left();
+        assert_eq!(&input.value()[input.cursor..], "fix  the bug ");
+        input.move_word_left();
+        assert_eq!(input.cursor, 0);
+
+        input.move_word_right();
+        assert_eq!(&input.value()[input.cursor..], "  the bug ");
+        input.move_end();
+        input.delete_word_before();
+        assert_eq!(input.value(), "  fix  the ");
+    }
+
+    #[test]
+    fn deleting_past_either_end_does_nothing() {
+        let mut input = LineInput::new("ok");
+        input.delete();
+        assert_eq!(input.value(), "ok");
+
+        input.move_home();
+        input.backspace();
+        input.delete_word_before();
+        input.move_left();
+        input.move_word_left();
+        assert_eq!(input.value(), "ok");
+        assert_eq!(input.cursor, 0);
+
+        let mut empty = LineInput::default();
+        empty.backspace();
+        empty.delete();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn wide_characters_are_single_graphemes() {
+        let mut input = LineInput::new("日本語");
+        input.move_left();
+        assert_eq!(&input.value()[..input.cursor], "日本");
+        input.insert_char('の');
+        assert_eq!(input.value(), "日本の語");
+        input.move_word_left();
+        assert_eq!(input.cursor, 0);
+    }
+
+    #[test]
+    fn pasted_line_breaks_become_spaces() {
+        let mut input = LineInput::default();
+        input.insert_str("one\r\ntwo\nthree");
+        assert_eq!(input.value(), "one two three");
+        assert!(input.cursor_at_end());
+    }
+}
@@ -1,24 +1,42 @@
-use crate::app::state::App;
+use crate::app::{
+    layout::ScreenLayout,
+    state::{App, Mode},
+};
+use crate::ui::details::render as render_details;
 use crate::ui::edit_popup::render as render_popup;
-use crate::ui::help_and_error::render as render_help_and_error;
+use crate::ui::help_and_error::{height as help_height, render as render_help_and_error};
+use crate::ui::prompt::render as render_prompt;
+use crate::ui::search::render as render_search;
+use crate::ui::search_results::render as render_search_results;
 use crate::ui::todos::render as render_todos;
+use crate::ui::trash::render as render_trash;
+use crate::ui::views::render as render_views;
 use ratatui::style::Style;
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::Text,
-    widgets::{Block, Padding, Paragraph},
+    widgets::{Block, Padding, Paragraph, Tabs},
     Frame,
 };
 
 pub fn render(f: &mut Frame, app: &mut App) {
+    app.layout = ScreenLayout::default();
+
     let constraints = if app.show_help || app.error_message.is_some() {
+        // the horizontal margin takes 3 columns on each side
+        let width = f.area().width.saturating_sub(6);
         vec![
             Constraint::Length(3),
+            Constraint::Length(1),
             Constraint::Min(10),
-            Constraint::Length(11),
+            Constraint::Length(help_height(app, width)),
         ]
     } else {
-        vec![Constraint::Length(3), Constraint::Min(10)]
+        vec![
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(10),
+        ]
     };
 
     let chunks = Layout::default()
@@ -34,11 +52,56 @@ pub fn render(f: &mut Frame, app: &mut App) {
 
     f.render_widget(title, chunks[0]);
 
-    render_todos(f, chunks[1], app);
+    render_project_tabs(f, chunks[1], app);
+
+    let showing_trash = app.mode == Mode::Trash
+        || app
+            .prompt
+            .as_ref()
+            .is_some_and(|p| p.kind.return_mode() == Mode::Trash);
+
+    if showing_trash {
+        render_trash(f, chunks[2], app);
+    } else if app.mode == Mode::SearchResults {
+        render_search_results(f, chunks[2], app);
+    } else {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(chunks[2]);
+        render_todos(f, panes[0], app);
+        render_search(f, panes[0], app);
+        render_details(f, panes[1], app);
+    }
 
     if app.show_help || app.error_message.is_some() {
-        render_help_and_error(f, chunks[2], &app);
+        render_help_and_error(f, chunks[3], app);
     }
 
     render_popup(f, f.area(), app);
+    render_views(f, f.area(), app);
+    render_prompt(f, f.area(), app);
+}
+
+fn render_project_tabs(f: &mut Frame, area: Rect, app: &App) {
+    let colors = &app.colors;
+
+    let titles: Vec<String> = app
+        .store
+        .projects()
+        .iter()
+        .map(|p| p.name.clone())
+        .collect();
+    let selected = app
+        .query
+        .project
+        .and_then(|id| app.store.projects().iter().position(|p| p.id == id));
+
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(colors.secondary_text())
+        .highlight_style(colors.selected())
+        .divider("|");
+
+    f.render_widget(tabs, area);
 }
@@ -1,4 +1,14 @@
+pub mod color_scheme;
+pub mod color_support;
+pub mod details;
 pub mod edit_popup;
 pub mod help_and_error;
+pub mod line_input;
 pub mod main;
+pub mod prompt;
+pub mod search;
+pub mod search_results;
+pub mod text_area;
 pub mod todos;
+pub mod trash;
+pub mod views;
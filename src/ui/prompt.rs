@@ -0,0 +1,51 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::state::App;
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let colors = &app.colors;
+
+    let Some(prompt) = &app.prompt else {
+        return;
+    };
+
+    let popup_area = prompt_rect(area);
+    frame.render_widget(Clear, popup_area);
+
+    let input = Paragraph::new(prompt.input.as_str())
+        .style(colors.highlighted_text())
+        .block(
+            Block::default()
+                .title(prompt.kind.title())
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(colors.selected_border()),
+        );
+
+    frame.render_widget(input, popup_area);
+}
+
+// a single line input, horizontally centered in the middle of the screen
+fn prompt_rect(r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .split(vertical[1])[1]
+}
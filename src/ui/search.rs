@@ -0,0 +1,72 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::state::App;
+
+/// Draws the search query over the bottom border of `area`, vim style
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
+    let colors = &app.colors;
+
+    if app.search.is_none() || area.height == 0 {
+        return;
+    }
+
+    let matches = app.search_matches();
+    let Some(search) = app.search.as_mut() else {
+        return;
+    };
+
+    let bar = Rect {
+        x: area.x + 1,
+        y: area.bottom() - 1,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+    frame.render_widget(Clear, bar);
+
+    let mut status = Vec::new();
+    if search.regex {
+        status.push(String::from("regex"));
+    }
+    if search.case_sensitive {
+        status.push(String::from("case"));
+    }
+    status.push(match &search.error {
+        Some(error) => error.clone(),
+        None if search.input.is_empty() => String::new(),
+        None => format!("{} matches", matches.len()),
+    });
+    let status = format!(" {} ", status.join(" | "));
+
+    let [slash, query, info] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(status.chars().count() as u16),
+        ])
+        .areas(bar);
+
+    frame.render_widget(Paragraph::new("/"), slash);
+    search.input.render(
+        frame,
+        query,
+        Block::default(),
+        colors.highlighted_text(),
+        None,
+        search.typing,
+    );
+    let style = if search.error.is_some() {
+        colors.error()
+    } else {
+        colors.secondary_text()
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(status, style))),
+        info,
+    );
+}
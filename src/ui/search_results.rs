@@ -0,0 +1,127 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, HighlightSpacing, Row, Table},
+    Frame,
+};
+
+use crate::{
+    app::state::{App, Mode},
+    models::todo::SearchHit,
+    ui::color_scheme::ColorScheme,
+};
+
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
+    let colors = &app.colors;
+
+    let Some(results) = app.results.as_mut() else {
+        return;
+    };
+
+    let result_rows: Vec<Row> = results
+        .hits
+        .iter()
+        .map(|hit| {
+            let todo = &hit.todo;
+            let status = if todo.deleted_at.is_some() {
+                "deleted"
+            } else if todo.completed {
+                "(✓)"
+            } else {
+                "( )"
+            };
+            let project = app
+                .store
+                .projects()
+                .iter()
+                .find(|p| p.id == todo.project_id)
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| String::from("(archived)"));
+
+            Row::new(vec![
+                Cell::from(status),
+                Cell::from(todo.text.clone()),
+                Cell::from(project),
+                Cell::from(snippet_line(hit, colors)),
+            ])
+            .height(2)
+            .style(if todo.deleted_at.is_some() {
+                colors.secondary_text()
+            } else {
+                Style::default().fg(colors.fg)
+            })
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Percentage(10),
+        Constraint::Percentage(30),
+        Constraint::Percentage(15),
+        Constraint::Percentage(45),
+    ];
+
+    let header_cells = vec![
+        Cell::from("Status"),
+        Cell::from("Task"),
+        Cell::from("Project"),
+        Cell::from("Match"),
+    ];
+
+    let title = format!(
+        "Search \"{}\" ({}{})",
+        results.query,
+        results.hits.len(),
+        if results.include_archived {
+            ", including deleted and archived"
+        } else {
+            ""
+        }
+    );
+
+    let results_table = Table::new(result_rows, widths)
+        .column_spacing(1)
+        .style(colors.fg)
+        .header(
+            Row::new(header_cells)
+                .style(colors.title())
+                .bottom_margin(1),
+        )
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(if app.mode == Mode::SearchResults {
+                    colors.selected_border()
+                } else {
+                    Style::default().bg(colors.bg)
+                }),
+        )
+        .row_highlight_style(colors.highlighted_row())
+        .highlight_symbol(">> ")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    frame.render_stateful_widget(results_table, area, &mut results.state);
+}
+
+// the snippet on a single line, with the matched words highlighted
+fn snippet_line<'a>(hit: &'a SearchHit, colors: &ColorScheme) -> Line<'a> {
+    let snippet = hit.snippet.as_str();
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in &hit.matches {
+        spans.push(Span::raw(&snippet[end..range.start]));
+        spans.push(Span::styled(&snippet[range.clone()], colors.search_match()));
+        end = range.end;
+    }
+    spans.push(Span::raw(&snippet[end..]));
+
+    // notes may span several lines, the table cell only shows one
+    for span in spans.iter_mut() {
+        if span.content.contains('\n') {
+            span.content = span.content.replace('\n', " ").into();
+        }
+    }
+    Line::from(spans)
+}
@@ -0,0 +1,348 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    style::Style,
+    text::Line,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::line_input::{next_grapheme, next_word, prev_grapheme, prev_word};
+
+/// A multi-line text input with a cursor, scrolled to keep the cursor in view
+pub struct TextArea {
+    lines: Vec<String>,
+    /// cursor line
+    row: usize,
+    /// cursor position within the line, as a byte index on a grapheme boundary
+    col: usize,
+    /// first line shown
+    scroll: usize,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.lines().map(String::from).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+
+        Self {
+            lines,
+            row: 0,
+            col: 0,
+            scroll: 0,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].len()
+    }
+
+    /// Moves the cursor onto `row`, keeping it as close to its current
+    /// display column as the line allows
+    fn move_to_row(&mut self, row: usize) {
+        let x = self.lines[self.row][..self.col].width();
+        self.row = row;
+        self.col = 0;
+        let line = &self.lines[row];
+        while self.col < line.len() {
+            let next = next_grapheme(line, self.col);
+            if line[..next].width() > x {
+                break;
+            }
+            self.col = next;
+        }
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.lines[self.row].insert(self.col, c);
+        self.col += c.len_utf8();
+    }
+
+    /// Inserts pasted text, which may span several lines
+    pub fn insert_str(&mut self, text: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.insert_newline();
+            }
+            let line = line.trim_end_matches('\r');
+            self.lines[self.row].insert_str(self.col, line);
+            self.col += line.len();
+        }
+    }
+
+    pub fn insert_newline(&mut self) {
+        let rest = self.lines[self.row].split_off(self.col);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    /// Deletes the grapheme before the cursor, joining lines at the start of a line
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            let start = prev_grapheme(&self.lines[self.row], self.col);
+            self.lines[self.row].replace_range(start..self.col, "");
+            self.col = start;
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len(self.row);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Deletes the grapheme under the cursor, joining lines at the end of a line
+    pub fn delete(&mut self) {
+        if self.col < self.line_len(self.row) {
+            let end = next_grapheme(&self.lines[self.row], self.col);
+            self.lines[self.row].replace_range(self.col..end, "");
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Deletes back to the start of the previous word on the current line
+    pub fn delete_word_before(&mut self) {
+        if self.col == 0 {
+            return self.backspace();
+        }
+        let start = prev_word(&self.lines[self.row], self.col);
+        self.lines[self.row].replace_range(start..self.col, "");
+        self.col = start;
+    }
+
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col = prev_grapheme(&self.lines[self.row], self.col);
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len(self.row);
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.col < self.line_len(self.row) {
+            self.col = next_grapheme(&self.lines[self.row], self.col);
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn move_word_left(&mut self) {
+        if self.col == 0 {
+            return self.move_left();
+        }
+        self.col = prev_word(&self.lines[self.row], self.col);
+    }
+
+    pub fn move_word_right(&mut self) {
+        if self.col == self.line_len(self.row) {
+            return self.move_right();
+        }
+        self.col = next_word(&self.lines[self.row], self.col);
+    }
+
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.move_to_row(self.row - 1);
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.move_to_row(self.row + 1);
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.col = self.line_len(self.row);
+    }
+
+    /// Applies an editing key, returning false if the key isn't one the
+    /// text area handles
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Char('w') if ctrl => self.delete_word_before(),
+            KeyCode::Char('b') if alt => self.move_word_left(),
+            KeyCode::Char('f') if alt => self.move_word_right(),
+            KeyCode::Backspace if alt => self.delete_word_before(),
+            KeyCode::Left if ctrl || alt => self.move_word_left(),
+            KeyCode::Right if ctrl || alt => self.move_word_right(),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_char(c),
+            KeyCode::Enter => self.insert_newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Up => self.move_up(),
+            KeyCode::Down => self.move_down(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Renders the text inside `block`, placing the terminal cursor if `focused`
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        style: Style,
+        focused: bool,
+    ) {
+        let inner = block.inner(area);
+        let height = inner.height as usize;
+
+        // keep the cursor line on screen
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if height > 0 && self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(height)
+            .map(|line| Line::from(line.as_str()))
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines).style(style).block(block), area);
+
+        if focused && height > 0 {
+            let x = self.lines[self.row][..self.col].width();
+            let col = x.min(inner.width.saturating_sub(1) as usize);
+            frame.set_cursor_position(Position::new(
+                inner.x + col as u16,
+                inner.y + (self.row - self.scroll) as u16,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(area: &TextArea) -> (usize, usize) {
+        (area.row, area.col)
+    }
+
+    #[test]
+    fn moves_by_grapheme_across_lines() {
+        let mut area = TextArea::new("ae\u{301}\nb");
+        area.move_end();
+        area.move_left();
+        assert_eq!(cursor(&area), (0, 1));
+
+        area.move_right();
+        area.move_right();
+        assert_eq!(cursor(&area), (1, 0));
+        area.move_left();
+        assert_eq!(cursor(&area), (0, "ae\u{301}".len()));
+    }
+
+    #[test]
+    fn moves_by_word_and_wraps_at_line_ends() {
+        let mut area = TextArea::new("call the bank\n  tomorrow");
+        area.move_word_right();
+        assert_eq!(cursor(&area), (0, 4));
+        area.move_end();
+        area.move_word_right();
+        assert_eq!(cursor(&area), (1, 0));
+        area.move_word_right();
+        assert_eq!(cursor(&area), (1, 10));
+
+        area.move_home();
+        area.move_word_left();
+        assert_eq!(cursor(&area), (0, 13));
+        area.delete_word_before();
+        assert_eq!(area.text(), "call the \n  tomorrow");
+    }
+
+    #[test]
+    fn deleting_at_line_edges_joins_lines() {
+        let mut area = TextArea::new("first\nsecond");
+        area.move_end();
+        area.delete();
+        assert_eq!(area.text(), "firstsecond");
+
+        area.insert_newline();
+        assert_eq!(cursor(&area), (1, 0));
+        area.backspace();
+        assert_eq!(area.text(), "firstsecond");
+        assert_eq!(cursor(&area), (0, 5));
+    }
+
+    #[test]
+    fn deleting_past_the_buffer_ends_does_nothing() {
+        let mut area = TextArea::new("only\nlines");
+        area.backspace();
+        area.delete_word_before();
+        area.move_left();
+        assert_eq!(cursor(&area), (0, 0));
+
+        area.move_down();
+        area.move_end();
+        area.delete();
+        area.move_right();
+        assert_eq!(area.text(), "only\nlines");
+        assert_eq!(cursor(&area), (1, 5));
+    }
+
+    #[test]
+    fn moving_up_and_down_keeps_the_display_column() {
+        let mut area = TextArea::new("日本語\nabcdef\nx");
+        area.move_right();
+        area.move_right();
+        area.move_down();
+        // two wide characters take four columns
+        assert_eq!(cursor(&area), (1, 4));
+
+        area.move_right();
+        area.move_up();
+        // column five falls inside the third wide character
+        assert_eq!(cursor(&area), (0, "日本".len()));
+
+        area.move_down();
+        area.move_down();
+        assert_eq!(cursor(&area), (2, 1));
+    }
+
+    #[test]
+    fn pasted_text_keeps_its_lines() {
+        let mut area = TextArea::default();
+        area.insert_str("one\r\ntwo");
+        assert_eq!(area.text(), "one\ntwo");
+        assert_eq!(cursor(&area), (1, 3));
+    }
+}
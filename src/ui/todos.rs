@@ -1,60 +1,82 @@
+use chrono::Local;
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Constraint, Flex, Layout, Rect},
     style::Style,
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, Cell, HighlightSpacing, Row, Table},
     Frame,
 };
 
 use crate::{
-    app::state::{App, Mode, SortCriteria},
-    models::color_scheme::ColorScheme,
+    app::state::{App, Mode},
+    models::{todo::Todo, view::SortCriteria},
+    ui::color_scheme::ColorScheme,
 };
 
-pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
-    let colors = ColorScheme::default();
+const ROW_HEIGHT: u16 = 2;
+const HIGHLIGHT_SYMBOL: &str = ">> ";
 
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
     app.sort_todos();
 
+    let widths = [
+        Constraint::Percentage(10),
+        Constraint::Percentage(40),
+        Constraint::Percentage(10),
+        Constraint::Percentage(20),
+        Constraint::Percentage(20),
+    ];
+
+    let block = Block::default()
+        .title(table_title(app))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+    cache_layout(app, block.inner(area), &widths);
+
+    let colors = &app.colors;
+    let block = block.border_style(if matches!(app.mode, Mode::Normal | Mode::Search) {
+        colors.selected_border()
+    } else {
+        Style::default().bg(colors.bg)
+    });
+
+    let now = Local::now();
     let todo_rows: Vec<Row> = app
-        .todos
+        .visible
         .iter()
-        .map(|todo| {
+        .zip(&app.depths)
+        .map(|(&i, &depth)| (&app.store.todos()[i], depth))
+        .map(|(todo, depth)| {
+            let due_style = if todo.is_overdue(now) {
+                colors.overdue_task()
+            } else if todo.is_due_today(now) {
+                colors.due_today_task()
+            } else {
+                Style::default()
+            };
+
             Row::new(vec![
                 Cell::from(if todo.completed { "(✓)" } else { "( )" }),
-                Cell::from(todo.text.clone()),
+                Cell::from(task_label(app, todo, depth, colors)),
                 Cell::from(format!("{}", todo.priority.unwrap())),
+                Cell::from(todo.due_display()).style(due_style),
+                Cell::from(todo.tags.join(", ")),
             ])
-            .height(2)
-            .style(Style::default().fg(if todo.completed {
-                colors.completed
+            .height(ROW_HEIGHT)
+            .style(if todo.completed {
+                colors.completed_task()
             } else {
-                colors.fg
-            }))
+                Style::default().fg(colors.fg)
+            })
         })
         .collect();
 
-    let widths = [
-        Constraint::Percentage(10),
-        Constraint::Percentage(65),
-        Constraint::Percentage(25),
-    ];
-
     let header_cells = vec![
-        Cell::from({
-            let mut status_header = String::from("Status");
-            if app.sort_by == SortCriteria::Completed {
-                status_header.push_str(if app.sort_asc { " ↑" } else { " ↓" })
-            }
-            status_header
-        }),
+        Cell::from(sort_header(app, "Status", SortCriteria::Completed)),
         Cell::from("Task"),
-        Cell::from({
-            let mut priority_header = String::from("Prioriy");
-            if app.sort_by == SortCriteria::Priority {
-                priority_header.push_str(if app.sort_asc { " ↑" } else { " ↓" });
-            }
-            priority_header
-        }),
+        Cell::from(sort_header(app, "Prioriy", SortCriteria::Priority)),
+        Cell::from(sort_header(app, "Due", SortCriteria::DueDate)),
+        Cell::from("Tags"),
     ];
 
     let todos_table = Table::new(todo_rows, widths)
@@ -65,20 +87,130 @@ pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
                 .style(colors.title())
                 .bottom_margin(1),
         )
-        .block(
-            Block::default()
-                .title("TODOs")
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(if app.mode == Mode::Normal {
-                    colors.selected_border()
-                } else {
-                    Style::default().bg(colors.bg)
-                }),
-        )
-        .row_highlight_style(Style::new().fg(colors.selection))
-        .highlight_symbol(">> ")
+        .block(block)
+        .row_highlight_style(colors.highlighted_row())
+        .highlight_symbol(HIGHLIGHT_SYMBOL)
         .highlight_spacing(HighlightSpacing::Always);
 
     frame.render_stateful_widget(todos_table, area, &mut app.table_state);
 }
+
+// indents subtasks under their parent, shows subtask progress on parents and
+// highlights search matches in the text
+fn task_label<'a>(app: &App, todo: &'a Todo, depth: usize, colors: &ColorScheme) -> Line<'a> {
+    let indent = "  ".repeat(depth);
+    let progress = app.subtask_progress(todo.id);
+
+    let prefix = match progress {
+        Some(_) => {
+            let marker = if app.is_collapsed(todo.id) {
+                "▸"
+            } else {
+                "▾"
+            };
+            format!("{}{} ", indent, marker)
+        }
+        None if depth > 0 => format!("{}  ", indent),
+        None => String::new(),
+    };
+
+    let mut spans = vec![Span::raw(prefix)];
+    let mut end = 0;
+    if let Some(search) = &app.search {
+        for range in search.match_ranges(&todo.text) {
+            spans.push(Span::raw(&todo.text[end..range.start]));
+            spans.push(Span::styled(
+                &todo.text[range.clone()],
+                colors.search_match(),
+            ));
+            end = range.end;
+        }
+    }
+    spans.push(Span::raw(&todo.text[end..]));
+
+    if let Some((done, total)) = progress {
+        spans.push(Span::raw(format!(" ({}/{} done)", done, total)));
+    }
+
+    Line::from(spans)
+}
+
+// remembers where the header cells and rows went, laying out the columns the
+// same way `Table` does: after the highlight symbol, one space apart
+fn cache_layout(app: &mut App, inner: Rect, widths: &[Constraint]) {
+    let [header, _, rows] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(inner);
+    let [_, columns] = Layout::horizontal([
+        Constraint::Length(HIGHLIGHT_SYMBOL.len() as u16),
+        Constraint::Fill(0),
+    ])
+    .areas(inner);
+    let columns = Layout::horizontal(widths.iter().copied())
+        .flex(Flex::Start)
+        .spacing(1)
+        .split(columns);
+
+    let layout = &mut app.layout;
+    layout.todo_rows = rows;
+    layout.todo_row_height = ROW_HEIGHT;
+    layout.status_column = Rect {
+        x: columns[0].x,
+        width: columns[0].width,
+        ..rows
+    };
+    layout.sort_headers = [
+        (0, SortCriteria::Completed),
+        (2, SortCriteria::Priority),
+        (3, SortCriteria::DueDate),
+    ]
+    .into_iter()
+    .map(|(column, criteria)| {
+        let cell = Rect {
+            x: columns[column].x,
+            width: columns[column].width,
+            ..header
+        };
+        (criteria, cell)
+    })
+    .collect();
+}
+
+// the column name with the direction of its sort key, numbered by precedence
+// when sorting by more than one key
+fn sort_header(app: &App, name: &str, criteria: SortCriteria) -> String {
+    let mut header = String::from(name);
+    if let Some((position, key)) = app.sort.find(criteria) {
+        header.push(' ');
+        header.push_str(key.arrow());
+        if app.sort.keys().len() > 1 {
+            header.push_str(&(position + 1).to_string());
+        }
+    }
+    header
+}
+
+// names the active tag filter and filter query, if any
+fn table_title(app: &App) -> String {
+    let mut title = String::from("TODOs");
+    if !app.query.tags.is_empty() {
+        title.push_str(&format!(" [tags: {}]", app.query.tags.join(", ")));
+    }
+    // created, completed and manual order have no column to show their arrow in
+    if app.sort.keys().len() > 1
+        || app.sort.find(SortCriteria::CreatedDate).is_some()
+        || app.sort.find(SortCriteria::CompletedDate).is_some()
+        || app.sort.find(SortCriteria::Manual).is_some()
+    {
+        title.push_str(&format!(" [sort: {}]", app.sort));
+    }
+    if let Some(view) = &app.active_view {
+        title.push_str(&format!(" [view: {}]", view));
+    } else if let Some(filter) = &app.query.filter {
+        title.push_str(&format!(" [filter: {}]", filter));
+    }
+    title
+}
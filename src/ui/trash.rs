@@ -0,0 +1,70 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::Style,
+    widgets::{Block, BorderType, Borders, Cell, HighlightSpacing, Row, Table},
+    Frame,
+};
+
+use crate::app::state::{App, Mode};
+
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
+    let colors = &app.colors;
+
+    let trash_rows: Vec<Row> = app
+        .trash
+        .iter()
+        .map(|todo| {
+            Row::new(vec![
+                Cell::from(if todo.completed { "(✓)" } else { "( )" }),
+                Cell::from(todo.text.clone()),
+                Cell::from(todo.priority.map(|p| p.to_string()).unwrap_or_default()),
+                Cell::from(
+                    todo.deleted_at
+                        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default(),
+                ),
+            ])
+            .height(2)
+            .style(colors.secondary_text())
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Percentage(10),
+        Constraint::Percentage(50),
+        Constraint::Percentage(15),
+        Constraint::Percentage(25),
+    ];
+
+    let header_cells = vec![
+        Cell::from("Status"),
+        Cell::from("Task"),
+        Cell::from("Priority"),
+        Cell::from("Deleted"),
+    ];
+
+    let trash_table = Table::new(trash_rows, widths)
+        .column_spacing(1)
+        .style(colors.fg)
+        .header(
+            Row::new(header_cells)
+                .style(colors.title())
+                .bottom_margin(1),
+        )
+        .block(
+            Block::default()
+                .title(format!("Trash ({})", app.trash.len()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(if app.mode == Mode::Trash {
+                    colors.selected_border()
+                } else {
+                    Style::default().bg(colors.bg)
+                }),
+        )
+        .row_highlight_style(colors.highlighted_row())
+        .highlight_symbol(">> ")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    frame.render_stateful_widget(trash_table, area, &mut app.trash_state);
+}
@@ -0,0 +1,85 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, HighlightSpacing, List, ListItem},
+    Frame,
+};
+
+use crate::app::state::{App, Mode};
+
+pub fn render(frame: &mut Frame, area: Rect, app: &mut App) {
+    let colors = &app.colors;
+
+    if app.mode != Mode::Views {
+        return;
+    }
+
+    let items: Vec<ListItem> = app
+        .views
+        .iter()
+        .map(|view| {
+            let marker = if app.active_view.as_ref() == Some(&view.name) {
+                "• "
+            } else {
+                "  "
+            };
+            let filter = if view.filter.is_empty() {
+                String::from("everything")
+            } else {
+                view.filter.clone()
+            };
+            let details = format!(
+                "  {}, by {}{}",
+                filter,
+                view.sort,
+                if view.is_builtin() { " (built-in)" } else { "" }
+            );
+
+            ListItem::new(Line::from(vec![
+                Span::raw(marker),
+                Span::styled(view.name.clone(), Style::default().fg(colors.fg)),
+                Span::styled(details, colors.secondary_text()),
+            ]))
+        })
+        .collect();
+
+    let popup_area = views_rect(area, app.views.len() as u16);
+    frame.render_widget(Clear, popup_area);
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title("Views")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(colors.selected_border()),
+        )
+        .style(Style::default().bg(colors.bg))
+        .highlight_style(colors.highlighted_row())
+        .highlight_symbol(">> ")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    frame.render_stateful_widget(list, popup_area, &mut app.views_state);
+}
+
+// tall enough for every view, horizontally centered in the middle of the screen
+fn views_rect(r: Rect, views: u16) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(views + 2),
+            Constraint::Fill(1),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .split(vertical[1])[1]
+}
@@ -0,0 +1,348 @@
+use std::{env, fs, process};
+
+use r_todo::{Filter, Priority, Query, StoreError, TodoFields, TodoStore, DEFAULT_PROJECT_ID};
+
+fn store() -> TodoStore {
+    TodoStore::open(":memory:").unwrap()
+}
+
+fn add(store: &mut TodoStore, text: &str) -> i64 {
+    store
+        .add(TodoFields::new(text), DEFAULT_PROJECT_ID)
+        .unwrap()
+}
+
+fn texts(todos: &[&r_todo::Todo]) -> Vec<String> {
+    let mut texts: Vec<String> = todos.iter().map(|t| t.text.clone()).collect();
+    texts.sort();
+    texts
+}
+
+#[test]
+fn added_todos_can_be_queried_by_project_tags_and_filter() {
+    let mut store = store();
+    let work = store.create_project("Work").unwrap();
+    store
+        .add(
+            TodoFields {
+                priority: Some(Priority::High),
+                tags: vec![String::from("infra")],
+                ..TodoFields::new("Renew certificates")
+            },
+            work,
+        )
+        .unwrap();
+    store
+        .add(
+            TodoFields {
+                tags: vec![String::from("home")],
+                ..TodoFields::new("Water plants")
+            },
+            DEFAULT_PROJECT_ID,
+        )
+        .unwrap();
+
+    assert_eq!(store.query(&Query::default()).len(), 2);
+
+    let in_work = Query {
+        project: Some(work),
+        ..Query::default()
+    };
+    assert_eq!(texts(&store.query(&in_work)), ["Renew certificates"]);
+
+    let tagged_home = Query {
+        tags: vec![String::from("home")],
+        ..Query::default()
+    };
+    assert_eq!(texts(&store.query(&tagged_home)), ["Water plants"]);
+
+    let high = Query {
+        filter: Some(Filter::parse("priority:high").unwrap()),
+        ..Query::default()
+    };
+    assert_eq!(texts(&store.query(&high)), ["Renew certificates"]);
+
+    assert_eq!(store.tags(), ["home", "infra"]);
+}
+
+#[test]
+fn archiving_a_project_hides_its_todos() {
+    let path = env::temp_dir().join(format!("r_todo-archive-test-{}.db", process::id()));
+    let path = path.to_str().unwrap();
+    let _ = fs::remove_file(path);
+
+    let mut store = TodoStore::open(path).unwrap();
+    let old = store.create_project("Old").unwrap();
+    let archived = store.add(TodoFields::new("Archived one"), old).unwrap();
+    add(&mut store, "Still here");
+
+    store.archive_project(old).unwrap();
+    assert_eq!(texts(&store.query(&Query::default())), ["Still here"]);
+    assert!(store.get(archived).is_none());
+    assert!(matches!(
+        store.archive_project(old),
+        Err(StoreError::NoSuchProject(id)) if id == old
+    ));
+    assert!(matches!(
+        store.rename_project(old, "Older"),
+        Err(StoreError::NoSuchProject(id)) if id == old
+    ));
+    assert!(matches!(
+        store.rename_project(999, "Nowhere"),
+        Err(StoreError::NoSuchProject(999))
+    ));
+
+    let store = TodoStore::open(path).unwrap();
+    assert_eq!(texts(&store.query(&Query::default())), ["Still here"]);
+    assert!(store.get(archived).is_none());
+
+    drop(store);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn update_replaces_the_fields() {
+    let mut store = store();
+    let id = add(&mut store, "Buy milk");
+
+    let fields = TodoFields {
+        priority: Some(Priority::Low),
+        tags: vec![String::from("shopping")],
+        notes: String::from("oat, not dairy"),
+        ..TodoFields::new("Buy oat milk")
+    };
+    store.update(id, fields).unwrap();
+
+    let todo = store.get(id).unwrap();
+    assert_eq!(todo.text, "Buy oat milk");
+    assert_eq!(todo.priority, Some(Priority::Low));
+    assert_eq!(todo.tags, ["shopping"]);
+    assert_eq!(todo.notes, "oat, not dairy");
+    assert_eq!(store.tags(), ["shopping"]);
+}
+
+#[test]
+fn completing_a_todo_completes_its_open_subtasks() {
+    let mut store = store();
+    let parent = add(&mut store, "Move house");
+    let child = store
+        .add_subtask(parent, TodoFields::new("Pack books"))
+        .unwrap();
+
+    store.complete(parent).unwrap();
+    assert!(store.get(parent).unwrap().completed);
+    assert!(store.get(child).unwrap().completed);
+    assert!(store.get(child).unwrap().completed_at.is_some());
+
+    store.reopen(parent).unwrap();
+    assert!(!store.get(parent).unwrap().completed);
+    assert!(store.get(child).unwrap().completed);
+}
+
+#[test]
+fn deleted_todos_go_to_the_trash_with_their_subtasks() {
+    let mut store = store();
+    let parent = add(&mut store, "Plan trip");
+    let child = store
+        .add_subtask(parent, TodoFields::new("Book hotel"))
+        .unwrap();
+    add(&mut store, "Call mum");
+
+    store.delete(parent).unwrap();
+    assert_eq!(texts(&store.query(&Query::default())), ["Call mum"]);
+    let mut trash: Vec<i64> = store.deleted().unwrap().iter().map(|t| t.id).collect();
+    trash.sort();
+    assert_eq!(trash, [parent, child]);
+
+    store.restore(parent).unwrap();
+    assert!(store.get(parent).is_some());
+    assert_eq!(store.get(child).unwrap().parent_id, Some(parent));
+    assert!(store.deleted().unwrap().is_empty());
+}
+
+#[test]
+fn restoring_a_todo_restores_the_subtasks_trashed_with_it() {
+    let mut store = store();
+    let parent = add(&mut store, "Plan trip");
+    let child = store
+        .add_subtask(parent, TodoFields::new("Book hotel"))
+        .unwrap();
+    let grandchild = store
+        .add_subtask(child, TodoFields::new("Compare prices"))
+        .unwrap();
+    let dropped = store
+        .add_subtask(parent, TodoFields::new("Rent a car"))
+        .unwrap();
+
+    store.delete(dropped).unwrap();
+    store.delete(parent).unwrap();
+    store.restore(parent).unwrap();
+    for id in [parent, child, grandchild] {
+        assert!(store.get(id).is_some());
+    }
+    // trashed on its own before the parent, so it stays there
+    assert!(store.get(dropped).is_none());
+    let trash: Vec<i64> = store.deleted().unwrap().iter().map(|t| t.id).collect();
+    assert_eq!(trash, [dropped]);
+
+    assert!(store.undo().unwrap());
+    for id in [parent, child, grandchild] {
+        assert!(store.get(id).is_none());
+    }
+    assert!(store.redo().unwrap());
+    for id in [parent, child, grandchild] {
+        assert!(store.get(id).is_some());
+    }
+}
+
+#[test]
+fn changes_can_be_undone_and_redone() {
+    let mut store = store();
+    let id = add(&mut store, "Write report");
+    store.complete(id).unwrap();
+
+    assert!(store.undo().unwrap());
+    assert!(!store.get(id).unwrap().completed);
+    assert!(store.undo().unwrap());
+    assert!(store.get(id).is_none());
+    assert!(!store.undo().unwrap());
+
+    assert!(store.redo().unwrap());
+    assert!(store.redo().unwrap());
+    assert!(store.get(id).unwrap().completed);
+    assert!(!store.redo().unwrap());
+}
+
+#[test]
+fn purging_a_todo_purges_its_subtasks_in_the_trash() {
+    let mut store = store();
+    let parent = add(&mut store, "Plan trip");
+    let child = store
+        .add_subtask(parent, TodoFields::new("Book hotel"))
+        .unwrap();
+    let grandchild = store
+        .add_subtask(child, TodoFields::new("Compare prices"))
+        .unwrap();
+    store.delete(parent).unwrap();
+
+    // a subtask brought back on its own outlives its purged parent
+    store.restore(grandchild).unwrap();
+    store.purge(parent).unwrap();
+    assert!(store.deleted().unwrap().is_empty());
+    assert_eq!(store.get(grandchild).unwrap().parent_id, None);
+
+    assert!(matches!(
+        store.purge(parent),
+        Err(StoreError::NoSuchTodo(id)) if id == parent
+    ));
+    assert!(matches!(
+        store.purge(grandchild),
+        Err(StoreError::NoSuchTodo(id)) if id == grandchild
+    ));
+}
+
+#[test]
+fn undoing_an_add_leaves_nothing_in_the_trash() {
+    let mut store = store();
+    let id = add(&mut store, "Typo'd todo");
+
+    assert!(store.undo().unwrap());
+    assert!(store.get(id).is_none());
+    assert!(store.deleted().unwrap().is_empty());
+
+    assert!(store.redo().unwrap());
+    assert_eq!(store.get(id).unwrap().text, "Typo'd todo");
+}
+
+#[test]
+fn purging_only_forgets_history_of_the_purged_todo() {
+    let mut store = store();
+    let kept = add(&mut store, "Keep me");
+    let purged = add(&mut store, "Purge me");
+    store.complete(kept).unwrap();
+    store.delete(purged).unwrap();
+
+    store.purge(purged).unwrap();
+    assert!(store.undo().unwrap());
+    assert!(!store.get(kept).unwrap().completed);
+    assert!(store.undo().unwrap());
+    assert!(store.get(kept).is_none());
+    assert!(!store.undo().unwrap());
+}
+
+#[test]
+fn unknown_ids_are_errors() {
+    let mut store = store();
+
+    assert!(matches!(
+        store.complete(42),
+        Err(StoreError::NoSuchTodo(42))
+    ));
+    assert!(matches!(
+        store.update(42, TodoFields::new("Nothing")),
+        Err(StoreError::NoSuchTodo(42))
+    ));
+    assert!(matches!(store.delete(42), Err(StoreError::NoSuchTodo(42))));
+    assert!(matches!(
+        store.add(TodoFields::new("Nowhere"), 42),
+        Err(StoreError::NoSuchProject(42))
+    ));
+    assert!(store.todos().is_empty());
+}
+
+#[test]
+fn todos_need_some_text() {
+    let mut store = store();
+    assert!(matches!(
+        store.add(TodoFields::new("  "), DEFAULT_PROJECT_ID),
+        Err(StoreError::EmptyText)
+    ));
+
+    let id = add(&mut store, "Something");
+    assert!(matches!(
+        store.update(id, TodoFields::new("")),
+        Err(StoreError::EmptyText)
+    ));
+    assert_eq!(store.get(id).unwrap().text, "Something");
+}
+
+#[test]
+fn full_text_search_finds_text_and_notes() {
+    let mut store = store();
+    add(&mut store, "Fix login bug");
+    let id = store
+        .add(
+            TodoFields {
+                notes: String::from("the login page times out"),
+                ..TodoFields::new("Investigate timeouts")
+            },
+            DEFAULT_PROJECT_ID,
+        )
+        .unwrap();
+    store.delete(id).unwrap();
+
+    assert_eq!(store.search("login", false).unwrap().len(), 1);
+    assert_eq!(store.search("login", true).unwrap().len(), 2);
+}
+
+#[test]
+fn todos_are_kept_in_the_database_file() {
+    let path = env::temp_dir().join(format!("r_todo-store-test-{}.db", process::id()));
+    let path = path.to_str().unwrap();
+    let _ = fs::remove_file(path);
+
+    let id = {
+        let mut store = TodoStore::open(path).unwrap();
+        let id = add(&mut store, "Survive a restart");
+        store.complete(id).unwrap();
+        id
+    };
+
+    let store = TodoStore::open(path).unwrap();
+    let todo = store.get(id).unwrap();
+    assert_eq!(todo.text, "Survive a restart");
+    assert!(todo.completed);
+
+    drop(store);
+    fs::remove_file(path).unwrap();
+}